@@ -1,7 +1,9 @@
 use clap::Parser;
+use regex::bytes::Regex;
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read, Write};
 
 #[derive(Parser, Debug)]
 #[command(name = "headr")]
@@ -12,70 +14,447 @@ pub struct Cli {
     #[arg(
         short = 'n',
         long = "lines",
-        help = "num lines to print",
-        default_value = "10"
+        help = "num lines to print (default 10), or with a leading '-' all but the last num lines",
+        allow_hyphen_values = true,
+        value_parser = parse_count
     )]
-    pub lines: usize,
+    pub lines: Option<Count>,
     #[arg(
         short = 'c',
         long = "bytes",
-        help = "num bytes to print",
-        conflicts_with = "lines"
+        help = "num bytes to print, or with a leading '-' all but the last num bytes",
+        conflicts_with = "lines",
+        allow_hyphen_values = true,
+        value_parser = parse_count
     )]
-    pub bytes: Option<usize>,
+    pub bytes: Option<Count>,
+    #[arg(
+        short = 'm',
+        long = "chars",
+        help = "num UTF-8 chars to print, or with a leading '-' all but the last num chars",
+        conflicts_with_all = ["lines", "bytes"],
+        allow_hyphen_values = true,
+        value_parser = parse_count
+    )]
+    pub chars: Option<Count>,
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        visible_alias = "silent",
+        help = "never print headers giving file names",
+        overrides_with = "verbose"
+    )]
+    pub quiet: bool,
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        help = "always print headers giving file names",
+        overrides_with = "quiet"
+    )]
+    pub verbose: bool,
+    #[arg(
+        long = "until",
+        value_name = "REGEX",
+        help = "print lines up to the first line matching REGEX",
+        value_parser = parse_regex,
+        conflicts_with_all = ["bytes", "chars", "while_matching"]
+    )]
+    pub until: Option<Regex>,
+    #[arg(
+        long = "exclusive",
+        help = "with --until, leave out the matching line",
+        requires = "until"
+    )]
+    pub exclusive: bool,
+    #[arg(
+        long = "while",
+        value_name = "REGEX",
+        help = "print lines for as long as they match REGEX",
+        value_parser = parse_regex,
+        conflicts_with_all = ["bytes", "chars"]
+    )]
+    pub while_matching: Option<Regex>,
+    #[arg(
+        short = 'z',
+        long = "zero-terminated",
+        help = "line delimiter is NUL, not newline"
+    )]
+    pub zero_terminated: bool,
     #[arg(help = "Input files", default_value = "-")]
     pub files: Vec<String>,
 }
 pub type MyResult<T> = Result<T, Box<dyn Error>>;
 
+/// How much of each input to print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Count {
+    /// The first N units.
+    First(usize),
+    /// Everything except the last N units, like `head -n -N`.
+    AllButLast(usize),
+}
+
+fn parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|_| format!("Invalid regex \"{}\"", s))
+}
+
+/// Multiplier suffixes accepted after a count, as in GNU head.
+const SUFFIXES: &[(&str, u64)] = &[
+    ("b", 512),
+    ("kB", 1000),
+    ("K", 1 << 10),
+    ("KiB", 1 << 10),
+    ("MB", 1000 * 1000),
+    ("M", 1 << 20),
+    ("MiB", 1 << 20),
+    ("GB", 1000 * 1000 * 1000),
+    ("G", 1 << 30),
+    ("GiB", 1 << 30),
+    ("TB", 1000 * 1000 * 1000 * 1000),
+    ("T", 1 << 40),
+    ("TiB", 1 << 40),
+    ("PB", 1000 * 1000 * 1000 * 1000 * 1000),
+    ("P", 1 << 50),
+    ("PiB", 1 << 50),
+    ("EB", 1000 * 1000 * 1000 * 1000 * 1000 * 1000),
+    ("E", 1 << 60),
+    ("EiB", 1 << 60),
+];
+
+fn parse_count(s: &str) -> Result<Count, String> {
+    let (negative, rest) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let split = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (digits, suffix) = rest.split_at(split);
+    if digits.is_empty() {
+        return Err(format!("Invalid number: {}", s));
+    }
+    let multiplier = match suffix {
+        "" => 1,
+        _ => SUFFIXES
+            .iter()
+            .find(|(name, _)| *name == suffix)
+            .map(|(_, mult)| *mult)
+            .ok_or_else(|| format!("Invalid suffix: {}", s))?,
+    };
+    let num = digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|num| num.checked_mul(multiplier))
+        .and_then(|num| usize::try_from(num).ok())
+        .ok_or_else(|| format!("Number too large: {}", s))?;
+    Ok(if negative {
+        Count::AllButLast(num)
+    } else {
+        Count::First(num)
+    })
+}
+
 pub fn run(args: Cli) -> MyResult<()> {
-    let num_files = args.files.len();
+    let pattern_mode = args.until.is_some() || args.while_matching.is_some();
+    // With a pattern, -n only caps the output and defaults to no limit.
+    let lines = args
+        .lines
+        .unwrap_or(Count::First(if pattern_mode { usize::MAX } else { 10 }));
+    if pattern_mode && matches!(lines, Count::AllButLast(_)) {
+        return Err(From::from(
+            "a negative line count cannot be used with --until or --while",
+        ));
+    }
+    let delim = if args.zero_terminated { b'\0' } else { b'\n' };
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let banners = !args.quiet && (args.verbose || args.files.len() > 1);
     for (file_num, filename) in args.files.iter().enumerate() {
-        match open(&filename) {
+        match open(filename) {
             Err(err) => eprintln!("failed to open {}: {}\n", filename, err),
             Ok(handle) => {
-                if num_files > 1 {
-                    println!(
+                if banners {
+                    writeln!(
+                        out,
                         "{}==> {} <==",
                         if file_num > 0 { "\n" } else { "" },
                         filename
-                    );
+                    )?;
+                }
+                match (args.bytes, args.chars) {
+                    (Some(Count::First(bytes)), _) => print_bytes(handle, bytes, &mut out)?,
+                    (Some(Count::AllButLast(bytes)), _) => {
+                        print_bytes_but_last(handle, bytes, &mut out)?
+                    }
+                    (None, Some(Count::First(chars))) => print_chars(handle, chars, &mut out)?,
+                    (None, Some(Count::AllButLast(chars))) => {
+                        print_chars_but_last(handle, chars, &mut out)?
+                    }
+                    (None, None) => match (lines, &args.until, &args.while_matching) {
+                        (Count::First(lines), Some(re), _) => {
+                            print_lines_until(handle, re, !args.exclusive, lines, delim, &mut out)?
+                        }
+                        (Count::First(lines), None, Some(re)) => {
+                            print_lines_while(handle, re, lines, delim, &mut out)?
+                        }
+                        (Count::First(lines), None, None) => {
+                            print_lines(handle, lines, delim, &mut out)?
+                        }
+                        (Count::AllButLast(lines), _, _) => {
+                            print_lines_but_last(handle, lines, delim, &mut out)?
+                        }
+                    },
                 }
-                if args.bytes.is_some() {
-                    print_bytes(handle, args.bytes.unwrap())?;
-                } else {
-                    print_lines(handle, args.lines)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_bytes(handle: Box<dyn BufRead>, bytes: usize, out: &mut impl Write) -> MyResult<()> {
+    io::copy(&mut handle.take(bytes as u64), out)?;
+    Ok(())
+}
+
+/// Prints everything except the last `bytes` bytes, holding back at most
+/// that many bytes at a time so unbounded streams work.
+fn print_bytes_but_last(
+    mut handle: Box<dyn BufRead>,
+    bytes: usize,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut held: VecDeque<u8> = VecDeque::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let bytes_read = handle.read(&mut buf)?;
+        if bytes_read == 0 {
+            break;
+        }
+        held.extend(&buf[..bytes_read]);
+        if held.len() > bytes {
+            let excess = held.len() - bytes;
+            let (front, back) = held.as_slices();
+            if excess <= front.len() {
+                out.write_all(&front[..excess])?;
+            } else {
+                out.write_all(front)?;
+                out.write_all(&back[..excess - front.len()])?;
+            }
+            held.drain(..excess);
+        }
+    }
+    Ok(())
+}
+
+/// True for bytes that are not UTF-8 continuation bytes. A character starts
+/// at each of these and at the first byte of input; any other continuation
+/// byte stays with the character before it, so output is always byte-exact.
+fn is_char_start(byte: u8) -> bool {
+    byte & 0b1100_0000 != 0b1000_0000
+}
+
+fn print_chars(mut handle: Box<dyn BufRead>, chars: usize, out: &mut impl Write) -> MyResult<()> {
+    let mut seen = 0;
+    let mut at_start = true;
+    loop {
+        let buf = handle.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let mut end = buf.len();
+        let mut done = false;
+        for (i, &byte) in buf.iter().enumerate() {
+            let starts = is_char_start(byte) || at_start;
+            at_start = false;
+            if starts {
+                if seen == chars {
+                    end = i;
+                    done = true;
+                    break;
                 }
+                seen += 1;
             }
         }
+        out.write_all(&buf[..end])?;
+        handle.consume(end);
+        if done {
+            break;
+        }
     }
     Ok(())
 }
 
-fn print_bytes(handle: Box<dyn BufRead>, bytes: usize) -> MyResult<()> {
-    let mut file = handle.take(bytes as u64);
-    let mut buf = vec![0u8; bytes];
-    let bytes_read = file.read(&mut buf)?;
-    print!("{}", String::from_utf8_lossy(&buf[..bytes_read]));
+/// Prints everything except the last `chars` characters, holding back only
+/// the bytes of those characters.
+fn print_chars_but_last(
+    mut handle: Box<dyn BufRead>,
+    chars: usize,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    if chars == 0 {
+        io::copy(&mut handle, out)?;
+        return Ok(());
+    }
+    let mut held: VecDeque<u8> = VecDeque::new();
+    let mut held_chars = 0;
+    let mut at_start = true;
+    for byte in handle.bytes() {
+        let byte = byte?;
+        let starts = is_char_start(byte) || at_start;
+        at_start = false;
+        if starts {
+            if held_chars == chars {
+                // Release the oldest held character to make room.
+                if let Some(first) = held.pop_front() {
+                    out.write_all(&[first])?;
+                }
+                while let Some(&next) = held.front() {
+                    if is_char_start(next) {
+                        break;
+                    }
+                    out.write_all(&[next])?;
+                    held.pop_front();
+                }
+            } else {
+                held_chars += 1;
+            }
+        }
+        held.push_back(byte);
+    }
     Ok(())
 }
 
-fn print_lines(mut handle: Box<dyn BufRead>, num_lines: usize) -> MyResult<()> {
-    let mut line = String::new();
+fn print_lines(
+    mut handle: Box<dyn BufRead>,
+    num_lines: usize,
+    delim: u8,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut line = Vec::new();
     for _ in 0..num_lines {
-        let bytes = handle.read_line(&mut line)?;
+        let bytes = handle.read_until(delim, &mut line)?;
         if bytes == 0 {
             break;
         }
-        print!("{}", line);
+        out.write_all(&line)?;
         line.clear();
     }
     Ok(())
 }
 
+/// Strips the record terminator so patterns can anchor on `$`.
+fn line_text(line: &[u8], delim: u8) -> &[u8] {
+    let line = line.strip_suffix(&[delim]).unwrap_or(line);
+    match delim {
+        b'\n' => line.strip_suffix(b"\r").unwrap_or(line),
+        _ => line,
+    }
+}
+
+/// Prints lines up to the first line matching `re`, stopping early after
+/// `max_lines`. The matching line itself is printed only when `inclusive`.
+fn print_lines_until(
+    mut handle: Box<dyn BufRead>,
+    re: &Regex,
+    inclusive: bool,
+    max_lines: usize,
+    delim: u8,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut line = Vec::new();
+    for _ in 0..max_lines {
+        if handle.read_until(delim, &mut line)? == 0 {
+            break;
+        }
+        if re.is_match(line_text(&line, delim)) {
+            if inclusive {
+                out.write_all(&line)?;
+            }
+            break;
+        }
+        out.write_all(&line)?;
+        line.clear();
+    }
+    Ok(())
+}
+
+/// Prints lines for as long as they match `re`, stopping early after
+/// `max_lines`.
+fn print_lines_while(
+    mut handle: Box<dyn BufRead>,
+    re: &Regex,
+    max_lines: usize,
+    delim: u8,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut line = Vec::new();
+    for _ in 0..max_lines {
+        if handle.read_until(delim, &mut line)? == 0 || !re.is_match(line_text(&line, delim)) {
+            break;
+        }
+        out.write_all(&line)?;
+        line.clear();
+    }
+    Ok(())
+}
+
+/// Prints everything except the last `num_lines` lines, keeping only that
+/// many lines in memory.
+fn print_lines_but_last(
+    mut handle: Box<dyn BufRead>,
+    num_lines: usize,
+    delim: u8,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut held: VecDeque<Vec<u8>> = VecDeque::new();
+    loop {
+        let mut line = Vec::new();
+        if handle.read_until(delim, &mut line)? == 0 {
+            break;
+        }
+        held.push_back(line);
+        if held.len() > num_lines {
+            if let Some(line) = held.pop_front() {
+                out.write_all(&line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     match filename {
         "-" => Ok(Box::new(BufReader::new(io::stdin()))),
         _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_count, Count};
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("3"), Ok(Count::First(3)));
+        assert_eq!(parse_count("0"), Ok(Count::First(0)));
+        assert_eq!(parse_count("-3"), Ok(Count::AllButLast(3)));
+        assert_eq!(parse_count("-0"), Ok(Count::AllButLast(0)));
+        assert!(parse_count("").is_err());
+        assert!(parse_count("-").is_err());
+        assert!(parse_count("+3").is_err());
+        assert!(parse_count("--3").is_err());
+        assert!(parse_count("3a").is_err());
+        assert!(parse_count("K").is_err());
+        assert!(parse_count("3 K").is_err());
+        assert!(parse_count("99999999999999999999").is_err());
+        assert!(parse_count("20E").is_err());
+
+        assert_eq!(parse_count("2b"), Ok(Count::First(1024)));
+        assert_eq!(parse_count("1K"), Ok(Count::First(1024)));
+        assert_eq!(parse_count("1KiB"), Ok(Count::First(1024)));
+        assert_eq!(parse_count("1kB"), Ok(Count::First(1000)));
+        assert_eq!(parse_count("1M"), Ok(Count::First(1 << 20)));
+        assert_eq!(parse_count("3MB"), Ok(Count::First(3_000_000)));
+        assert_eq!(parse_count("-1G"), Ok(Count::AllButLast(1 << 30)));
+    }
+}
@@ -1,11 +1,7 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::io::prelude::*;
-use std::{
-    error::Error,
-    fs::{self, File},
-};
+use std::{error::Error, fs};
 
 type TestResult = Result<(), Box<dyn Error>>;
 
@@ -15,6 +11,9 @@ const ONE: &str = "./tests/inputs/one.txt";
 const TWO: &str = "./tests/inputs/two.txt";
 const THREE: &str = "./tests/inputs/three.txt";
 const TEN: &str = "./tests/inputs/ten.txt";
+const BINARY: &str = "./tests/inputs/binary.bin";
+const MAIL: &str = "./tests/inputs/mail.txt";
+const FIND0: &str = "./tests/inputs/find0.bin";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -41,7 +40,7 @@ fn dies_bad_bytes() -> TestResult {
     let bad = random_string();
     let expected = format!("invalid value '{}'", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-c", &bad, EMPTY])
+        .args(["-c", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -55,7 +54,7 @@ fn dies_bad_lines() -> TestResult {
     let bad = random_string();
     let expected = format!("invalid value '{}'", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-n", &bad, EMPTY])
+        .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -69,7 +68,7 @@ fn dies_bytes_and_lines() -> TestResult {
     let msg = "the argument '--lines <LINES>' cannot be used with '--bytes <BYTES>'";
 
     Command::cargo_bin(PRG)?
-        .args(&["-n", "1", "-c", "2"])
+        .args(["-n", "1", "-c", "2"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(msg));
@@ -92,35 +91,27 @@ fn skips_bad_file() -> TestResult {
 
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
+    let expected = fs::read(expected_file)?;
 
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(predicate::eq(expected));
 
     Ok(())
 }
 
 // --------------------------------------------------
 fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
-    let input = fs::read_to_string(input_file)?;
+    let expected = fs::read(expected_file)?;
+    let input = fs::read(input_file)?;
 
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .args(args)
         .assert()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(predicate::eq(expected));
 
     Ok(())
 }
@@ -414,3 +405,251 @@ fn multiple_files_c4() -> TestResult {
         "tests/expected/all.c4.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_negative_lines() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "-x", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '-x'"));
+
+    Ok(())
+}
+
+#[test]
+fn ten_n_minus3() -> TestResult {
+    run(&[TEN, "-n", "-3"], "tests/expected/ten.txt.n-3.out")
+}
+
+#[test]
+fn ten_n_minus3_stdin() -> TestResult {
+    run_stdin(&["-n", "-3"], TEN, "tests/expected/ten.txt.n-3.out")
+}
+
+#[test]
+fn three_c_minus4() -> TestResult {
+    run(&[THREE, "-c", "-4"], "tests/expected/three.txt.c-4.out")
+}
+
+#[test]
+fn three_c_minus4_stdin() -> TestResult {
+    run_stdin(&["-c", "-4"], THREE, "tests/expected/three.txt.c-4.out")
+}
+
+#[test]
+fn multiple_files_n_minus2() -> TestResult {
+    run(
+        &["-n", "-2", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.n-2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_chars_and_bytes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-m", "1", "-c", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
+#[test]
+fn binary_c12() -> TestResult {
+    run(&[BINARY, "-c", "12"], "tests/expected/binary.bin.c12.out")
+}
+
+#[test]
+fn binary_c12_stdin() -> TestResult {
+    run_stdin(&["-c", "12"], BINARY, "tests/expected/binary.bin.c12.out")
+}
+
+#[test]
+fn one_m1() -> TestResult {
+    run(&[ONE, "-m", "1"], "tests/expected/one.txt.m1.out")
+}
+
+#[test]
+fn one_m1_stdin() -> TestResult {
+    run_stdin(&["--chars", "1"], ONE, "tests/expected/one.txt.m1.out")
+}
+
+#[test]
+fn one_m_minus3() -> TestResult {
+    run(&[ONE, "-m", "-3"], "tests/expected/one.txt.m-3.out")
+}
+
+#[test]
+fn leading_continuation_byte_is_a_char() -> TestResult {
+    // Both directions agree that "\x80ab" is three characters.
+    for (args, expected) in [
+        (["-m", "1"], &b"\x80"[..]),
+        (["-m", "2"], b"\x80a"),
+        (["-m", "-2"], b"\x80"),
+        (["-m", "-1"], b"\x80a"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .write_stdin(&b"\x80ab"[..])
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_suffix() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "1X", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '1X'"));
+
+    Ok(())
+}
+
+#[test]
+fn ten_c1k() -> TestResult {
+    run(&[TEN, "-c", "1K"], "tests/expected/ten.txt.c1K.out")
+}
+
+#[test]
+fn one_verbose() -> TestResult {
+    run(&["-v", ONE], "tests/expected/one.txt.v.out")
+}
+
+#[test]
+fn multiple_files_n2_quiet() -> TestResult {
+    run(
+        &["--quiet", "-n", "2", ONE, TEN],
+        "tests/expected/all.n2.q.out",
+    )
+}
+
+#[test]
+fn multiple_files_verbose_then_quiet() -> TestResult {
+    run(
+        &["-v", "-q", "-n", "2", ONE, TEN],
+        "tests/expected/all.n2.q.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_until() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--until", "(", MAIL])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid regex"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_until_negative_lines() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--until", "^$", "-n", "-2", MAIL])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("negative line count"));
+
+    Ok(())
+}
+
+#[test]
+fn mail_until() -> TestResult {
+    run(
+        &["--until", "^$", MAIL],
+        "tests/expected/mail.txt.until.out",
+    )
+}
+
+#[test]
+fn mail_until_exclusive() -> TestResult {
+    run(
+        &["--until", "^$", "--exclusive", MAIL],
+        "tests/expected/mail.txt.until.exclusive.out",
+    )
+}
+
+#[test]
+fn mail_until_n2() -> TestResult {
+    run(
+        &["--until", "^$", "-n", "2", MAIL],
+        "tests/expected/mail.txt.until.n2.out",
+    )
+}
+
+#[test]
+fn mail_while_stdin() -> TestResult {
+    run_stdin(
+        &["--while", "^[A-Z][a-z]*: "],
+        MAIL,
+        "tests/expected/mail.txt.while.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_n1() -> TestResult {
+    run(&[BINARY, "-n", "1"], "tests/expected/binary.bin.n1.out")
+}
+
+#[test]
+fn find0_z_n2() -> TestResult {
+    run(
+        &[FIND0, "-z", "-n", "2"],
+        "tests/expected/find0.bin.z.n2.out",
+    )
+}
+
+#[test]
+fn find0_z_n2_stdin() -> TestResult {
+    run_stdin(
+        &["--zero-terminated", "-n", "2"],
+        FIND0,
+        "tests/expected/find0.bin.z.n2.out",
+    )
+}
+
+#[test]
+fn find0_z_n_minus1() -> TestResult {
+    run(
+        &[FIND0, "-z", "-n", "-1"],
+        "tests/expected/find0.bin.z.n-1.out",
+    )
+}
+
+// --------------------------------------------------
+fn huge_but_last(args: &[&str]) -> TestResult {
+    // Counts far beyond the input must not be allocated up front.
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+#[test]
+fn stdin_c_minus_1e() -> TestResult {
+    huge_but_last(&["-c", "-1E"])
+}
+
+#[test]
+fn stdin_n_minus_1e() -> TestResult {
+    huge_but_last(&["-n", "-1E"])
+}
+
+#[test]
+fn stdin_n_minus_max() -> TestResult {
+    huge_but_last(&["-n", "-18446744073709551615"])
+}
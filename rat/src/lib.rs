@@ -1,32 +1,131 @@
+use clap::Parser;
 use std::error::Error;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::fs::{self, File, FileType};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use walkdir::WalkDir;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
-pub fn run(files: Vec<String>, number_lines: bool, number_nonblank: bool) -> MyResult<()> {
-    for filename in files {
-        match open(&filename) {
-            Err(err) => eprintln!("Failed to open {}: {}", filename, err),
-            Ok(handle) => {
-                let mut last_line = 0;
-                for (line_num, line_raw) in handle.lines().enumerate() {
-                    let line = line_raw?;
-                    if number_lines {
-                        println!("{:6}\t{}", line_num + 1, line);
-                    } else if number_nonblank {
-                        if line.is_empty() {
-                            println!();
-                        } else {
-                            last_line += 1;
-                            println!("{:6}\t{}", last_line, line);
-                        }
-                    } else {
-                        println!("{}", line);
-                    }
-                }
+#[derive(Parser, Debug)]
+#[command(name = "rat")]
+#[command(author = "Takkaryx")]
+#[command(version = "1.0")]
+#[command(about = "rust cat", long_about = None)]
+pub struct Cli {
+    #[arg(short = 'n', long = "number", help = "num lines")]
+    pub number_lines: bool,
+    #[arg(short = 'b', long = "number-nonblank", help = "num non-blank lines")]
+    pub number_nonblank: bool,
+    #[arg(
+        short = 'r',
+        long = "recursive",
+        help = "Concatenate every regular file beneath directories"
+    )]
+    pub recursive: bool,
+    #[arg(help = "Input files")]
+    pub files: Vec<String>,
+}
+
+/// Options controlling what rat reads and how each line is numbered.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Config {
+    pub files: Vec<String>,
+    pub number_lines: bool,
+    pub number_nonblank: bool,
+    pub recursive: bool,
+}
+
+impl From<Cli> for Config {
+    fn from(args: Cli) -> Self {
+        Config {
+            files: args.files,
+            number_lines: args.number_lines,
+            number_nonblank: args.number_nonblank,
+            recursive: args.recursive,
+        }
+    }
+}
+
+pub fn run(config: &Config) -> MyResult<()> {
+    let stdout = io::stdout();
+    run_to(config, &mut stdout.lock())
+}
+
+/// Concatenates every file in `config` into `out`. Files that fail to open
+/// are reported on stderr and skipped.
+pub fn run_to<W: Write>(config: &Config, out: &mut W) -> MyResult<()> {
+    for filename in &config.files {
+        if config.recursive && filename != "-" && Path::new(filename).is_dir() {
+            cat_dir(config, filename, out)?;
+        } else {
+            cat_file(config, filename, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints every regular file beneath `dir`, and every symlink to one, in sorted
+/// order, each preceded by a `==> name <==` banner.
+fn cat_dir<W: Write>(config: &Config, dir: &str, out: &mut W) -> MyResult<()> {
+    let entries = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| match e {
+            Err(e) => {
+                eprintln!("{}", e);
+                None
             }
+            Ok(entry) => Some(entry),
+        })
+        // Symlinks count when they point at a regular file.
+        .filter(|entry| entry.path().is_file());
+
+    for (file_num, entry) in entries.enumerate() {
+        let filename = entry.path().display().to_string();
+        writeln!(
+            out,
+            "{}==> {} <==",
+            if file_num > 0 { "\n" } else { "" },
+            filename
+        )?;
+        cat_file(config, &filename, out)?;
+    }
+    Ok(())
+}
+
+fn cat_file<W: Write>(config: &Config, filename: &str, out: &mut W) -> MyResult<()> {
+    match open(filename) {
+        Err(err) => eprintln!("Failed to open {}: {}", filename, err),
+        Ok(handle) => cat(config, filename, handle, out)?,
+    }
+    Ok(())
+}
+
+/// Copies `handle` to `out` line by line as raw bytes, so files that are not
+/// UTF-8 pass through unchanged.
+fn cat<W: Write>(
+    config: &Config,
+    filename: &str,
+    mut handle: impl BufRead,
+    out: &mut W,
+) -> MyResult<()> {
+    let mut last_line = 0;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let bytes = handle
+            .read_until(b'\n', &mut line)
+            .map_err(|err| read_error(filename, err))?;
+        if bytes == 0 {
+            break;
+        }
+        let blank = line == b"\n";
+        if config.number_lines || config.number_nonblank && !blank {
+            last_line += 1;
+            write!(out, "{:6}\t", last_line)?;
         }
+        out.write_all(&line)?;
     }
     Ok(())
 }
@@ -34,6 +133,105 @@ pub fn run(files: Vec<String>, number_lines: bool, number_nonblank: bool) -> MyR
 fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     match filename {
         "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+        _ => {
+            // Opening a directory or socket fails with an opaque error,
+            // so name the type instead.
+            let file_type = fs::metadata(filename)?.file_type();
+            if file_type.is_dir() {
+                return Err(From::from("Is a directory"));
+            }
+            if is_socket(file_type) {
+                return Err(From::from("Is a socket"));
+            }
+            Ok(Box::new(BufReader::new(File::open(filename)?)))
+        }
+    }
+}
+
+/// Names the file in a read error, along with its type when it is not a
+/// regular file, since devices and pipes fail in less familiar ways.
+fn read_error(filename: &str, err: io::Error) -> String {
+    match fs::metadata(filename)
+        .ok()
+        .and_then(|m| special_kind(m.file_type()))
+    {
+        Some(kind) => format!("Failed to read {} ({}): {}", filename, kind, err),
+        None => format!("Failed to read {}: {}", filename, err),
+    }
+}
+
+#[cfg(unix)]
+fn is_socket(file_type: FileType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    file_type.is_socket()
+}
+
+#[cfg(not(unix))]
+fn is_socket(_file_type: FileType) -> bool {
+    false
+}
+
+/// Describes file types other than regular files and directories.
+#[cfg(unix)]
+fn special_kind(file_type: FileType) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if file_type.is_fifo() {
+        Some("named pipe")
+    } else if file_type.is_char_device() {
+        Some("character device")
+    } else if file_type.is_block_device() {
+        Some("block device")
+    } else if file_type.is_socket() {
+        Some("socket")
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn special_kind(_file_type: FileType) -> Option<&'static str> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_to, Config};
+
+    fn render(config: &Config) -> String {
+        let mut out = Vec::new();
+        run_to(config, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_run_to_plain() {
+        let config = Config {
+            files: vec!["tests/inputs/fox.txt".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&config),
+            "The quick brown fox jumps over the lazy dog.\n"
+        );
+    }
+
+    #[test]
+    fn test_run_to_numbered() {
+        let config = Config {
+            files: vec!["tests/inputs/the-bustle.txt".to_string()],
+            number_nonblank: true,
+            ..Default::default()
+        };
+        let expected = std::fs::read_to_string("tests/expected/the-bustle.txt.b.out").unwrap();
+        assert_eq!(render(&config), expected);
+
+        let config = Config {
+            number_nonblank: false,
+            number_lines: true,
+            ..config
+        };
+        let expected = std::fs::read_to_string("tests/expected/the-bustle.txt.n.out").unwrap();
+        assert_eq!(render(&config), expected);
     }
 }
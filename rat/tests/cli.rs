@@ -194,3 +194,88 @@ fn all_n() -> TestResult {
 fn all_b() -> TestResult {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn skips_directory() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", FOX])
+        .assert()
+        .success()
+        .stdout("The quick brown fox jumps over the lazy dog.\n")
+        .stderr(predicate::str::contains("tests/inputs: Is a directory"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dir_recursive() -> TestResult {
+    run(&["-r", "tests/inputs"], "tests/expected/inputs.r.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn dir_recursive_n() -> TestResult {
+    run(
+        &["--recursive", "-n", "tests/inputs"],
+        "tests/expected/inputs.r.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn reads_char_device() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["/dev/null", FOX])
+        .assert()
+        .success()
+        .stdout("The quick brown fox jumps over the lazy dog.\n")
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reads_fifo() -> TestResult {
+    let fifo = std::env::temp_dir().join(format!("rat-{}", gen_bad_file()));
+    assert!(std::process::Command::new("mkfifo")
+        .arg(&fifo)
+        .status()?
+        .success());
+
+    let writer = {
+        let fifo = fifo.clone();
+        std::thread::spawn(move || fs::write(fifo, "hi\n"))
+    };
+    let output = Command::cargo_bin(PRG)?
+        .arg("-n")
+        .arg(&fifo)
+        .timeout(std::time::Duration::from_secs(5))
+        .output()?;
+    writer.join().unwrap()?;
+    fs::remove_file(&fifo)?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "     1\thi\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dir_recursive_binary_and_symlink() -> TestResult {
+    let dir = std::env::temp_dir().join(format!("rat-{}", gen_bad_file()));
+    fs::create_dir(&dir)?;
+    fs::write(dir.join("1.txt"), "one\n")?;
+    fs::write(dir.join("2.bin"), b"\xff\xfe")?;
+    fs::write(dir.join("3.txt"), "three\n")?;
+    std::os::unix::fs::symlink(dir.join("1.txt"), dir.join("4.link"))?;
+
+    let output = Command::cargo_bin(PRG)?.arg("-r").arg(&dir).output()?;
+    fs::remove_dir_all(&dir)?;
+    assert!(output.status.success());
+    let name = dir.display();
+    let mut expected = format!("==> {name}/1.txt <==\none\n\n==> {name}/2.bin <==\n").into_bytes();
+    expected.extend_from_slice(b"\xff\xfe\n");
+    expected.extend(format!("==> {name}/3.txt <==\nthree\n\n==> {name}/4.link <==\none\n").bytes());
+    assert_eq!(output.stdout, expected);
+    Ok(())
+}
@@ -0,0 +1,526 @@
+use clap::Parser;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Parser, Debug)]
+#[command(name = "tailr")]
+#[command(author = "Takkaryx")]
+#[command(version = "1.0")]
+#[command(about = "rust tail", long_about = None)]
+pub struct Cli {
+    #[arg(
+        short = 'n',
+        long = "lines",
+        help = "num lines to print, or with a leading '+' start at line num",
+        default_value = "10",
+        allow_hyphen_values = true,
+        value_parser = parse_take
+    )]
+    pub lines: Take,
+    #[arg(
+        short = 'c',
+        long = "bytes",
+        help = "num bytes to print, or with a leading '+' start at byte num",
+        conflicts_with = "lines",
+        allow_hyphen_values = true,
+        value_parser = parse_take
+    )]
+    pub bytes: Option<Take>,
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        visible_alias = "silent",
+        help = "never print headers giving file names",
+        overrides_with = "verbose"
+    )]
+    pub quiet: bool,
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        help = "always print headers giving file names",
+        overrides_with = "quiet"
+    )]
+    pub verbose: bool,
+    #[arg(
+        short = 'f',
+        long = "follow",
+        help = "keep regular files open and print data as it is appended; stdin and pipes are read once"
+    )]
+    pub follow: bool,
+    #[arg(
+        short = 'F',
+        help = "follow by name, reopening files that are truncated, rotated or recreated"
+    )]
+    pub follow_name: bool,
+    #[arg(
+        short = 's',
+        long = "sleep-interval",
+        value_name = "SECS",
+        help = "with -f, seconds to sleep between polls",
+        default_value = "1.0",
+        value_parser = parse_interval
+    )]
+    pub sleep_interval: Duration,
+    #[arg(
+        long = "pid",
+        value_name = "PID",
+        help = "with -f, exit once process PID has died",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub pid: Option<u32>,
+    #[arg(help = "Input files", default_value = "-")]
+    pub files: Vec<String>,
+}
+pub type MyResult<T> = Result<T, Box<dyn Error>>;
+
+/// Which part of each input to print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Take {
+    /// The last N units.
+    Last(u64),
+    /// Everything from the Nth unit (1-based) onwards, like `tail -n +N`.
+    From(u64),
+}
+
+fn parse_take(s: &str) -> Result<Take, String> {
+    let (from, digits) = match s.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('-').unwrap_or(s)),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("Invalid number: {}", s));
+    }
+    let num = digits.parse::<u64>().map_err(|e| e.to_string())?;
+    Ok(if from {
+        Take::From(num)
+    } else {
+        Take::Last(num)
+    })
+}
+
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    s.parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| format!("Invalid interval: {}", s))
+}
+
+/// An opened input. Regular files are read by seeking from the end; anything
+/// else has to be streamed through.
+enum Input {
+    Seekable(File),
+    Stream(Box<dyn BufRead>),
+}
+
+pub fn run(args: Cli) -> MyResult<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let banners = !args.quiet && (args.verbose || args.files.len() > 1);
+    let follow_name = args.follow_name;
+    if cfg!(not(unix)) && args.pid.is_some() {
+        return Err(From::from("--pid is not supported on this platform"));
+    }
+    let mut followed = Vec::new();
+    let mut streams = 0;
+    for (file_num, filename) in args.files.iter().enumerate() {
+        match open(filename) {
+            Err(err) => {
+                eprintln!("failed to open {}: {}\n", filename, err);
+                if follow_name && filename != "-" {
+                    followed.push(Followed::missing(file_num, filename));
+                }
+            }
+            Ok(input) => {
+                if banners {
+                    writeln!(
+                        out,
+                        "{}==> {} <==",
+                        if file_num > 0 { "\n" } else { "" },
+                        filename
+                    )?;
+                }
+                match (input, args.bytes) {
+                    (Input::Seekable(mut file), take) => {
+                        match take {
+                            Some(take) => seek_bytes(&mut file, take, &mut out)?,
+                            None => seek_lines(&mut file, args.lines, &mut out)?,
+                        }
+                        followed.push(Followed::opened(file_num, filename, file)?);
+                    }
+                    (Input::Stream(handle), take) => {
+                        match take {
+                            Some(take) => stream_bytes(handle, take, &mut out)?,
+                            None => stream_lines(handle, args.lines, &mut out)?,
+                        }
+                        streams += 1;
+                    }
+                }
+            }
+        }
+    }
+    // Only regular files are followed. Stdin and named pipes are read to end
+    // of file and dropped, so -f does nothing when they are all there is.
+    if (args.follow || follow_name) && streams < args.files.len() {
+        if !follow_name && followed.iter().all(|f| f.file.is_none()) {
+            out.flush()?;
+            return Err(From::from("no files remaining"));
+        }
+        let last_shown = args.files.len().saturating_sub(1);
+        follow(
+            &mut followed,
+            &FollowOpts {
+                by_name: follow_name,
+                banners,
+                interval: args.sleep_interval,
+                pid: args.pid,
+            },
+            last_shown,
+            &mut out,
+        )?;
+    }
+    Ok(())
+}
+
+/// A regular file being watched for appended data.
+struct Followed {
+    file_num: usize,
+    name: String,
+    file: Option<File>,
+    pos: u64,
+    id: Option<FileId>,
+}
+
+impl Followed {
+    fn opened(file_num: usize, name: &str, mut file: File) -> MyResult<Self> {
+        let pos = file.stream_position()?;
+        let id = file_id(&file.metadata()?);
+        Ok(Followed {
+            file_num,
+            name: name.to_string(),
+            file: Some(file),
+            pos,
+            id,
+        })
+    }
+
+    fn missing(file_num: usize, name: &str) -> Self {
+        Followed {
+            file_num,
+            name: name.to_string(),
+            file: None,
+            pos: 0,
+            id: None,
+        }
+    }
+}
+
+struct FollowOpts {
+    /// Track each file by name rather than by open descriptor (`-F`).
+    by_name: bool,
+    banners: bool,
+    interval: Duration,
+    pid: Option<u32>,
+}
+
+/// Polls every followed file and prints whatever was appended since the last
+/// pass. Runs until `opts.pid` dies, or forever without one.
+fn follow(
+    files: &mut [Followed],
+    opts: &FollowOpts,
+    mut last_shown: usize,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    loop {
+        // Sample liveness first so data written just before the process
+        // exited still gets printed on this last pass.
+        let alive = opts.pid.is_none_or(process_alive);
+        for followed in files.iter_mut() {
+            print_appended(followed, opts.banners, &mut last_shown, out)?;
+            // Finish the old file before switching, so lines written just
+            // before a rotation are not lost.
+            if opts.by_name && check_name(followed) {
+                print_appended(followed, opts.banners, &mut last_shown, out)?;
+            }
+        }
+        out.flush()?;
+        if !alive {
+            return Ok(());
+        }
+        thread::sleep(opts.interval);
+    }
+}
+
+/// Prints anything written to `followed` since the last call, starting over
+/// from the top if the file was truncated.
+fn print_appended(
+    followed: &mut Followed,
+    banners: bool,
+    last_shown: &mut usize,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let Some(file) = followed.file.as_mut() else {
+        return Ok(());
+    };
+    let len = file.metadata()?.len();
+    if len < followed.pos {
+        eprintln!("tailr: {}: file truncated", followed.name);
+        followed.pos = 0;
+    }
+    if len == followed.pos {
+        return Ok(());
+    }
+    if banners && followed.file_num != *last_shown {
+        writeln!(out, "\n==> {} <==", followed.name)?;
+        *last_shown = followed.file_num;
+    }
+    file.seek(SeekFrom::Start(followed.pos))?;
+    followed.pos += io::copy(&mut file.take(len - followed.pos), out)?;
+    Ok(())
+}
+
+/// Reopens `followed` when the name now points at a different file, and
+/// drops it while the name is missing. Returns true if a new file was opened.
+fn check_name(followed: &mut Followed) -> bool {
+    match fs::metadata(&followed.name) {
+        Err(_) => {
+            if followed.file.take().is_some() {
+                eprintln!("tailr: {} has become inaccessible", followed.name);
+            }
+            false
+        }
+        Ok(meta) => {
+            let id = file_id(&meta);
+            if followed.file.is_some() && id == followed.id {
+                return false;
+            }
+            if let Ok(file) = File::open(&followed.name) {
+                eprintln!(
+                    "tailr: {} has {}; following new file",
+                    followed.name,
+                    if followed.id.is_some() {
+                        "been replaced"
+                    } else {
+                        "appeared"
+                    }
+                );
+                followed.file = Some(file);
+                followed.pos = 0;
+                followed.id = id;
+                return true;
+            }
+            false
+        }
+    }
+}
+
+/// Identifies the file behind a name so rotation can be detected.
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = ();
+
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> Option<FileId> {
+    None
+}
+
+/// Probes `pid` with signal 0, which checks for the process without
+/// signalling it. `EPERM` means it exists but belongs to another user.
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only performs the existence and permission checks.
+    let ret = unsafe { libc::kill(pid, 0) };
+    ret == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// `run` rejects `--pid` on these platforms.
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Byte offset where output starts for `take` in a file of `len` bytes.
+fn byte_start(take: Take, len: u64) -> u64 {
+    match take {
+        Take::Last(num) => len.saturating_sub(num),
+        Take::From(num) => num.saturating_sub(1).min(len),
+    }
+}
+
+fn seek_bytes(file: &mut File, take: Take, out: &mut impl Write) -> MyResult<()> {
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(byte_start(take, len)))?;
+    io::copy(file, out)?;
+    Ok(())
+}
+
+fn seek_lines(file: &mut File, take: Take, out: &mut impl Write) -> MyResult<()> {
+    match take {
+        Take::Last(num) => {
+            let start = last_lines_start(file, num)?;
+            file.seek(SeekFrom::Start(start))?;
+            io::copy(file, out)?;
+            Ok(())
+        }
+        Take::From(_) => stream_lines(BufReader::new(file), take, out),
+    }
+}
+
+/// Finds the offset of the first of the last `num` lines by scanning
+/// backwards from the end in blocks, so only the tail of the file is read.
+fn last_lines_start(file: &mut File, num: u64) -> MyResult<u64> {
+    const BLOCK: u64 = 64 * 1024;
+
+    let len = file.metadata()?.len();
+    if num == 0 {
+        return Ok(len);
+    }
+    let mut buf = vec![0u8; BLOCK as usize];
+    let mut end = len;
+    let mut found = 0;
+    // A trailing newline ends the last line rather than starting a new one.
+    let mut skip_last = true;
+    while end > 0 {
+        let start = end.saturating_sub(BLOCK);
+        let chunk = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        for (i, &byte) in chunk.iter().enumerate().rev() {
+            if skip_last {
+                skip_last = false;
+                if byte == b'\n' {
+                    continue;
+                }
+            }
+            if byte == b'\n' {
+                found += 1;
+                if found == num {
+                    return Ok(start + i as u64 + 1);
+                }
+            }
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+/// Prints the last bytes of a stream, keeping only that many bytes in memory.
+fn stream_bytes(mut handle: Box<dyn BufRead>, take: Take, out: &mut impl Write) -> MyResult<()> {
+    match take {
+        Take::From(num) => {
+            io::copy(
+                &mut (&mut handle).take(num.saturating_sub(1)),
+                &mut io::sink(),
+            )?;
+            io::copy(&mut handle, out)?;
+        }
+        Take::Last(num) => {
+            let num = usize::try_from(num).unwrap_or(usize::MAX);
+            let mut held: VecDeque<u8> = VecDeque::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                let bytes_read = handle.read(&mut buf)?;
+                if bytes_read == 0 {
+                    break;
+                }
+                held.extend(&buf[..bytes_read]);
+                if held.len() > num {
+                    held.drain(..held.len() - num);
+                }
+            }
+            let (front, back) = held.as_slices();
+            out.write_all(front)?;
+            out.write_all(back)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the last lines of a stream using a ring buffer of `num` lines.
+fn stream_lines(mut handle: impl BufRead, take: Take, out: &mut impl Write) -> MyResult<()> {
+    match take {
+        Take::From(num) => {
+            let mut line = Vec::new();
+            for _ in 1..num {
+                if handle.read_until(b'\n', &mut line)? == 0 {
+                    return Ok(());
+                }
+                line.clear();
+            }
+            io::copy(&mut handle, out)?;
+        }
+        Take::Last(num) => {
+            let num = usize::try_from(num).unwrap_or(usize::MAX);
+            let mut held: VecDeque<Vec<u8>> = VecDeque::new();
+            loop {
+                let mut line = Vec::new();
+                if handle.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                held.push_back(line);
+                if held.len() > num {
+                    held.pop_front();
+                }
+            }
+            for line in held {
+                out.write_all(&line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn open(filename: &str) -> MyResult<Input> {
+    match filename {
+        "-" => Ok(Input::Stream(Box::new(BufReader::new(io::stdin())))),
+        _ => {
+            let file = File::open(filename)?;
+            if file.metadata()?.is_file() {
+                Ok(Input::Seekable(file))
+            } else {
+                Ok(Input::Stream(Box::new(BufReader::new(file))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{byte_start, parse_take, Take};
+
+    #[test]
+    fn test_parse_take() {
+        assert_eq!(parse_take("3"), Ok(Take::Last(3)));
+        assert_eq!(parse_take("-3"), Ok(Take::Last(3)));
+        assert_eq!(parse_take("+3"), Ok(Take::From(3)));
+        assert_eq!(parse_take("+0"), Ok(Take::From(0)));
+        assert_eq!(parse_take("0"), Ok(Take::Last(0)));
+        assert!(parse_take("").is_err());
+        assert!(parse_take("+").is_err());
+        assert!(parse_take("+-3").is_err());
+        assert!(parse_take("3a").is_err());
+    }
+
+    #[test]
+    fn test_byte_start() {
+        assert_eq!(byte_start(Take::Last(3), 10), 7);
+        assert_eq!(byte_start(Take::Last(30), 10), 0);
+        assert_eq!(byte_start(Take::From(0), 10), 0);
+        assert_eq!(byte_start(Take::From(1), 10), 0);
+        assert_eq!(byte_start(Take::From(4), 10), 3);
+        assert_eq!(byte_start(Take::From(40), 10), 10);
+    }
+}
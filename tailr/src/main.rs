@@ -0,0 +1,9 @@
+use clap::Parser;
+
+fn main() {
+    let args = tailr::Cli::parse();
+    if let Err(e) = tailr::run(args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
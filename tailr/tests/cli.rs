@@ -0,0 +1,807 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use rand::{distributions::Alphanumeric, Rng};
+use std::io::Write;
+use std::{error::Error, fs, process, thread, time::Duration};
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+const PRG: &str = "tailr";
+const EMPTY: &str = "./tests/inputs/empty.txt";
+const ONE: &str = "./tests/inputs/one.txt";
+const TWO: &str = "./tests/inputs/two.txt";
+const THREE: &str = "./tests/inputs/three.txt";
+const TEN: &str = "./tests/inputs/ten.txt";
+const BINARY: &str = "./tests/inputs/binary.bin";
+
+// --------------------------------------------------
+fn random_string() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(7)
+        .map(char::from)
+        .collect()
+}
+
+// --------------------------------------------------
+fn gen_bad_file() -> String {
+    loop {
+        let filename = random_string();
+        if fs::metadata(&filename).is_err() {
+            return filename;
+        }
+    }
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_bytes() -> TestResult {
+    let bad = random_string();
+    let expected = format!("invalid value '{}'", &bad);
+    Command::cargo_bin(PRG)?
+        .args(["-c", &bad, EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_lines() -> TestResult {
+    let bad = random_string();
+    let expected = format!("invalid value '{}'", &bad);
+    Command::cargo_bin(PRG)?
+        .args(["-n", &bad, EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bytes_and_lines() -> TestResult {
+    let msg = "the argument '--lines <LINES>' cannot be used with '--bytes <BYTES>'";
+
+    Command::cargo_bin(PRG)?
+        .args(["-n", "1", "-c", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(msg));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("{}: .* [(]os error 2[)]", bad);
+    Command::cargo_bin(PRG)?
+        .args([EMPTY, &bad, ONE])
+        .assert()
+        .stderr(predicate::str::is_match(expected)?);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(predicate::eq(expected));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+    let input = fs::read(input_file)?;
+
+    Command::cargo_bin(PRG)?
+        .write_stdin(input)
+        .args(args)
+        .assert()
+        .stdout(predicate::eq(expected));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn empty() -> TestResult {
+    run(&[EMPTY], "tests/expected/empty.txt.out")
+}
+
+#[test]
+fn empty_stdin() -> TestResult {
+    run_stdin(&[], EMPTY, "tests/expected/empty.txt.out")
+}
+
+#[test]
+fn empty_n0() -> TestResult {
+    run(&[EMPTY, "-n", "0"], "tests/expected/empty.txt.n0.out")
+}
+
+#[test]
+fn empty_n0_stdin() -> TestResult {
+    run_stdin(&["-n", "0"], EMPTY, "tests/expected/empty.txt.n0.out")
+}
+
+#[test]
+fn empty_n1() -> TestResult {
+    run(&[EMPTY, "-n", "1"], "tests/expected/empty.txt.n1.out")
+}
+
+#[test]
+fn empty_n1_stdin() -> TestResult {
+    run_stdin(&["-n", "1"], EMPTY, "tests/expected/empty.txt.n1.out")
+}
+
+#[test]
+fn empty_n3() -> TestResult {
+    run(&[EMPTY, "-n", "3"], "tests/expected/empty.txt.n3.out")
+}
+
+#[test]
+fn empty_n3_stdin() -> TestResult {
+    run_stdin(&["-n", "3"], EMPTY, "tests/expected/empty.txt.n3.out")
+}
+
+#[test]
+fn empty_nplus1() -> TestResult {
+    run(&[EMPTY, "-n", "+1"], "tests/expected/empty.txt.n+1.out")
+}
+
+#[test]
+fn empty_nplus1_stdin() -> TestResult {
+    run_stdin(&["-n", "+1"], EMPTY, "tests/expected/empty.txt.n+1.out")
+}
+
+#[test]
+fn empty_nplus3() -> TestResult {
+    run(&[EMPTY, "-n", "+3"], "tests/expected/empty.txt.n+3.out")
+}
+
+#[test]
+fn empty_nplus3_stdin() -> TestResult {
+    run_stdin(&["-n", "+3"], EMPTY, "tests/expected/empty.txt.n+3.out")
+}
+
+#[test]
+fn empty_c1() -> TestResult {
+    run(&[EMPTY, "-c", "1"], "tests/expected/empty.txt.c1.out")
+}
+
+#[test]
+fn empty_c1_stdin() -> TestResult {
+    run_stdin(&["-c", "1"], EMPTY, "tests/expected/empty.txt.c1.out")
+}
+
+#[test]
+fn empty_c3() -> TestResult {
+    run(&[EMPTY, "-c", "3"], "tests/expected/empty.txt.c3.out")
+}
+
+#[test]
+fn empty_c3_stdin() -> TestResult {
+    run_stdin(&["-c", "3"], EMPTY, "tests/expected/empty.txt.c3.out")
+}
+
+#[test]
+fn empty_c8() -> TestResult {
+    run(&[EMPTY, "-c", "8"], "tests/expected/empty.txt.c8.out")
+}
+
+#[test]
+fn empty_c8_stdin() -> TestResult {
+    run_stdin(&["-c", "8"], EMPTY, "tests/expected/empty.txt.c8.out")
+}
+
+#[test]
+fn empty_cplus3() -> TestResult {
+    run(&[EMPTY, "-c", "+3"], "tests/expected/empty.txt.c+3.out")
+}
+
+#[test]
+fn empty_cplus3_stdin() -> TestResult {
+    run_stdin(&["-c", "+3"], EMPTY, "tests/expected/empty.txt.c+3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn one() -> TestResult {
+    run(&[ONE], "tests/expected/one.txt.out")
+}
+
+#[test]
+fn one_stdin() -> TestResult {
+    run_stdin(&[], ONE, "tests/expected/one.txt.out")
+}
+
+#[test]
+fn one_n0() -> TestResult {
+    run(&[ONE, "-n", "0"], "tests/expected/one.txt.n0.out")
+}
+
+#[test]
+fn one_n0_stdin() -> TestResult {
+    run_stdin(&["-n", "0"], ONE, "tests/expected/one.txt.n0.out")
+}
+
+#[test]
+fn one_n1() -> TestResult {
+    run(&[ONE, "-n", "1"], "tests/expected/one.txt.n1.out")
+}
+
+#[test]
+fn one_n1_stdin() -> TestResult {
+    run_stdin(&["-n", "1"], ONE, "tests/expected/one.txt.n1.out")
+}
+
+#[test]
+fn one_n3() -> TestResult {
+    run(&[ONE, "-n", "3"], "tests/expected/one.txt.n3.out")
+}
+
+#[test]
+fn one_n3_stdin() -> TestResult {
+    run_stdin(&["-n", "3"], ONE, "tests/expected/one.txt.n3.out")
+}
+
+#[test]
+fn one_nplus1() -> TestResult {
+    run(&[ONE, "-n", "+1"], "tests/expected/one.txt.n+1.out")
+}
+
+#[test]
+fn one_nplus1_stdin() -> TestResult {
+    run_stdin(&["-n", "+1"], ONE, "tests/expected/one.txt.n+1.out")
+}
+
+#[test]
+fn one_nplus3() -> TestResult {
+    run(&[ONE, "-n", "+3"], "tests/expected/one.txt.n+3.out")
+}
+
+#[test]
+fn one_nplus3_stdin() -> TestResult {
+    run_stdin(&["-n", "+3"], ONE, "tests/expected/one.txt.n+3.out")
+}
+
+#[test]
+fn one_c1() -> TestResult {
+    run(&[ONE, "-c", "1"], "tests/expected/one.txt.c1.out")
+}
+
+#[test]
+fn one_c1_stdin() -> TestResult {
+    run_stdin(&["-c", "1"], ONE, "tests/expected/one.txt.c1.out")
+}
+
+#[test]
+fn one_c3() -> TestResult {
+    run(&[ONE, "-c", "3"], "tests/expected/one.txt.c3.out")
+}
+
+#[test]
+fn one_c3_stdin() -> TestResult {
+    run_stdin(&["-c", "3"], ONE, "tests/expected/one.txt.c3.out")
+}
+
+#[test]
+fn one_c8() -> TestResult {
+    run(&[ONE, "-c", "8"], "tests/expected/one.txt.c8.out")
+}
+
+#[test]
+fn one_c8_stdin() -> TestResult {
+    run_stdin(&["-c", "8"], ONE, "tests/expected/one.txt.c8.out")
+}
+
+#[test]
+fn one_cplus3() -> TestResult {
+    run(&[ONE, "-c", "+3"], "tests/expected/one.txt.c+3.out")
+}
+
+#[test]
+fn one_cplus3_stdin() -> TestResult {
+    run_stdin(&["-c", "+3"], ONE, "tests/expected/one.txt.c+3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn two() -> TestResult {
+    run(&[TWO], "tests/expected/two.txt.out")
+}
+
+#[test]
+fn two_stdin() -> TestResult {
+    run_stdin(&[], TWO, "tests/expected/two.txt.out")
+}
+
+#[test]
+fn two_n0() -> TestResult {
+    run(&[TWO, "-n", "0"], "tests/expected/two.txt.n0.out")
+}
+
+#[test]
+fn two_n0_stdin() -> TestResult {
+    run_stdin(&["-n", "0"], TWO, "tests/expected/two.txt.n0.out")
+}
+
+#[test]
+fn two_n1() -> TestResult {
+    run(&[TWO, "-n", "1"], "tests/expected/two.txt.n1.out")
+}
+
+#[test]
+fn two_n1_stdin() -> TestResult {
+    run_stdin(&["-n", "1"], TWO, "tests/expected/two.txt.n1.out")
+}
+
+#[test]
+fn two_n3() -> TestResult {
+    run(&[TWO, "-n", "3"], "tests/expected/two.txt.n3.out")
+}
+
+#[test]
+fn two_n3_stdin() -> TestResult {
+    run_stdin(&["-n", "3"], TWO, "tests/expected/two.txt.n3.out")
+}
+
+#[test]
+fn two_nplus1() -> TestResult {
+    run(&[TWO, "-n", "+1"], "tests/expected/two.txt.n+1.out")
+}
+
+#[test]
+fn two_nplus1_stdin() -> TestResult {
+    run_stdin(&["-n", "+1"], TWO, "tests/expected/two.txt.n+1.out")
+}
+
+#[test]
+fn two_nplus3() -> TestResult {
+    run(&[TWO, "-n", "+3"], "tests/expected/two.txt.n+3.out")
+}
+
+#[test]
+fn two_nplus3_stdin() -> TestResult {
+    run_stdin(&["-n", "+3"], TWO, "tests/expected/two.txt.n+3.out")
+}
+
+#[test]
+fn two_c1() -> TestResult {
+    run(&[TWO, "-c", "1"], "tests/expected/two.txt.c1.out")
+}
+
+#[test]
+fn two_c1_stdin() -> TestResult {
+    run_stdin(&["-c", "1"], TWO, "tests/expected/two.txt.c1.out")
+}
+
+#[test]
+fn two_c3() -> TestResult {
+    run(&[TWO, "-c", "3"], "tests/expected/two.txt.c3.out")
+}
+
+#[test]
+fn two_c3_stdin() -> TestResult {
+    run_stdin(&["-c", "3"], TWO, "tests/expected/two.txt.c3.out")
+}
+
+#[test]
+fn two_c8() -> TestResult {
+    run(&[TWO, "-c", "8"], "tests/expected/two.txt.c8.out")
+}
+
+#[test]
+fn two_c8_stdin() -> TestResult {
+    run_stdin(&["-c", "8"], TWO, "tests/expected/two.txt.c8.out")
+}
+
+#[test]
+fn two_cplus3() -> TestResult {
+    run(&[TWO, "-c", "+3"], "tests/expected/two.txt.c+3.out")
+}
+
+#[test]
+fn two_cplus3_stdin() -> TestResult {
+    run_stdin(&["-c", "+3"], TWO, "tests/expected/two.txt.c+3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn three() -> TestResult {
+    run(&[THREE], "tests/expected/three.txt.out")
+}
+
+#[test]
+fn three_stdin() -> TestResult {
+    run_stdin(&[], THREE, "tests/expected/three.txt.out")
+}
+
+#[test]
+fn three_n0() -> TestResult {
+    run(&[THREE, "-n", "0"], "tests/expected/three.txt.n0.out")
+}
+
+#[test]
+fn three_n0_stdin() -> TestResult {
+    run_stdin(&["-n", "0"], THREE, "tests/expected/three.txt.n0.out")
+}
+
+#[test]
+fn three_n1() -> TestResult {
+    run(&[THREE, "-n", "1"], "tests/expected/three.txt.n1.out")
+}
+
+#[test]
+fn three_n1_stdin() -> TestResult {
+    run_stdin(&["-n", "1"], THREE, "tests/expected/three.txt.n1.out")
+}
+
+#[test]
+fn three_n3() -> TestResult {
+    run(&[THREE, "-n", "3"], "tests/expected/three.txt.n3.out")
+}
+
+#[test]
+fn three_n3_stdin() -> TestResult {
+    run_stdin(&["-n", "3"], THREE, "tests/expected/three.txt.n3.out")
+}
+
+#[test]
+fn three_nplus1() -> TestResult {
+    run(&[THREE, "-n", "+1"], "tests/expected/three.txt.n+1.out")
+}
+
+#[test]
+fn three_nplus1_stdin() -> TestResult {
+    run_stdin(&["-n", "+1"], THREE, "tests/expected/three.txt.n+1.out")
+}
+
+#[test]
+fn three_nplus3() -> TestResult {
+    run(&[THREE, "-n", "+3"], "tests/expected/three.txt.n+3.out")
+}
+
+#[test]
+fn three_nplus3_stdin() -> TestResult {
+    run_stdin(&["-n", "+3"], THREE, "tests/expected/three.txt.n+3.out")
+}
+
+#[test]
+fn three_c1() -> TestResult {
+    run(&[THREE, "-c", "1"], "tests/expected/three.txt.c1.out")
+}
+
+#[test]
+fn three_c1_stdin() -> TestResult {
+    run_stdin(&["-c", "1"], THREE, "tests/expected/three.txt.c1.out")
+}
+
+#[test]
+fn three_c3() -> TestResult {
+    run(&[THREE, "-c", "3"], "tests/expected/three.txt.c3.out")
+}
+
+#[test]
+fn three_c3_stdin() -> TestResult {
+    run_stdin(&["-c", "3"], THREE, "tests/expected/three.txt.c3.out")
+}
+
+#[test]
+fn three_c8() -> TestResult {
+    run(&[THREE, "-c", "8"], "tests/expected/three.txt.c8.out")
+}
+
+#[test]
+fn three_c8_stdin() -> TestResult {
+    run_stdin(&["-c", "8"], THREE, "tests/expected/three.txt.c8.out")
+}
+
+#[test]
+fn three_cplus3() -> TestResult {
+    run(&[THREE, "-c", "+3"], "tests/expected/three.txt.c+3.out")
+}
+
+#[test]
+fn three_cplus3_stdin() -> TestResult {
+    run_stdin(&["-c", "+3"], THREE, "tests/expected/three.txt.c+3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten() -> TestResult {
+    run(&[TEN], "tests/expected/ten.txt.out")
+}
+
+#[test]
+fn ten_stdin() -> TestResult {
+    run_stdin(&[], TEN, "tests/expected/ten.txt.out")
+}
+
+#[test]
+fn ten_n0() -> TestResult {
+    run(&[TEN, "-n", "0"], "tests/expected/ten.txt.n0.out")
+}
+
+#[test]
+fn ten_n0_stdin() -> TestResult {
+    run_stdin(&["-n", "0"], TEN, "tests/expected/ten.txt.n0.out")
+}
+
+#[test]
+fn ten_n1() -> TestResult {
+    run(&[TEN, "-n", "1"], "tests/expected/ten.txt.n1.out")
+}
+
+#[test]
+fn ten_n1_stdin() -> TestResult {
+    run_stdin(&["-n", "1"], TEN, "tests/expected/ten.txt.n1.out")
+}
+
+#[test]
+fn ten_n3() -> TestResult {
+    run(&[TEN, "-n", "3"], "tests/expected/ten.txt.n3.out")
+}
+
+#[test]
+fn ten_n3_stdin() -> TestResult {
+    run_stdin(&["-n", "3"], TEN, "tests/expected/ten.txt.n3.out")
+}
+
+#[test]
+fn ten_nplus1() -> TestResult {
+    run(&[TEN, "-n", "+1"], "tests/expected/ten.txt.n+1.out")
+}
+
+#[test]
+fn ten_nplus1_stdin() -> TestResult {
+    run_stdin(&["-n", "+1"], TEN, "tests/expected/ten.txt.n+1.out")
+}
+
+#[test]
+fn ten_nplus3() -> TestResult {
+    run(&[TEN, "-n", "+3"], "tests/expected/ten.txt.n+3.out")
+}
+
+#[test]
+fn ten_nplus3_stdin() -> TestResult {
+    run_stdin(&["-n", "+3"], TEN, "tests/expected/ten.txt.n+3.out")
+}
+
+#[test]
+fn ten_c1() -> TestResult {
+    run(&[TEN, "-c", "1"], "tests/expected/ten.txt.c1.out")
+}
+
+#[test]
+fn ten_c1_stdin() -> TestResult {
+    run_stdin(&["-c", "1"], TEN, "tests/expected/ten.txt.c1.out")
+}
+
+#[test]
+fn ten_c3() -> TestResult {
+    run(&[TEN, "-c", "3"], "tests/expected/ten.txt.c3.out")
+}
+
+#[test]
+fn ten_c3_stdin() -> TestResult {
+    run_stdin(&["-c", "3"], TEN, "tests/expected/ten.txt.c3.out")
+}
+
+#[test]
+fn ten_c8() -> TestResult {
+    run(&[TEN, "-c", "8"], "tests/expected/ten.txt.c8.out")
+}
+
+#[test]
+fn ten_c8_stdin() -> TestResult {
+    run_stdin(&["-c", "8"], TEN, "tests/expected/ten.txt.c8.out")
+}
+
+#[test]
+fn ten_cplus3() -> TestResult {
+    run(&[TEN, "-c", "+3"], "tests/expected/ten.txt.c+3.out")
+}
+
+#[test]
+fn ten_cplus3_stdin() -> TestResult {
+    run_stdin(&["-c", "+3"], TEN, "tests/expected/ten.txt.c+3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_c5() -> TestResult {
+    run(&[BINARY, "-c", "5"], "tests/expected/binary.bin.c5.out")
+}
+
+#[test]
+fn binary_c5_stdin() -> TestResult {
+    run_stdin(&["-c", "5"], BINARY, "tests/expected/binary.bin.c5.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files() -> TestResult {
+    run(&[EMPTY, ONE, TWO, THREE, TEN], "tests/expected/all.out")
+}
+
+#[test]
+fn multiple_files_n1() -> TestResult {
+    run(
+        &["-n", "1", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.n1.out",
+    )
+}
+
+#[test]
+fn multiple_files_c_plus3() -> TestResult {
+    run(&["-c", "+3", ONE, TEN], "tests/expected/all.c+3.out")
+}
+
+#[test]
+fn multiple_files_n1_quiet() -> TestResult {
+    run(&["-q", "-n", "1", ONE, TEN], "tests/expected/all.n1.q.out")
+}
+
+// --------------------------------------------------
+fn dead_pid() -> Result<u32, Box<dyn Error>> {
+    let mut child = process::Command::new("true").spawn()?;
+    let pid = child.id();
+    child.wait()?;
+    Ok(pid)
+}
+
+#[test]
+fn dies_bad_sleep_interval() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "--sleep-interval=-1", TEN])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '-1'"));
+
+    Ok(())
+}
+
+#[test]
+fn follow_exits_with_dead_pid() -> TestResult {
+    let pid = dead_pid()?.to_string();
+    run(
+        &["-f", "--pid", &pid, "-n", "3", TEN],
+        "tests/expected/ten.txt.n3.out",
+    )
+}
+
+#[test]
+fn follow_name_appends_and_rotation() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("service.log");
+    fs::write(&log, "one\ntwo\n")?;
+
+    let mut watched = process::Command::new("sleep").arg("2").spawn()?;
+    let tailr = process::Command::new(cargo_bin(PRG))
+        .args(["-F", "-s", "0.1", "-n", "1", "--pid"])
+        .arg(watched.id().to_string())
+        .arg(&log)
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()?;
+
+    thread::sleep(Duration::from_millis(500));
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&log)?
+        .write_all(b"three\n")?;
+    thread::sleep(Duration::from_millis(500));
+    fs::rename(&log, dir.path().join("service.log.1"))?;
+    fs::write(&log, "four\n")?;
+
+    watched.wait()?;
+    let output = tailr.wait_with_output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "two\nthree\nfour\n");
+    assert!(String::from_utf8(output.stderr)?.contains("has been replaced"));
+
+    Ok(())
+}
+
+#[test]
+fn follow_detects_truncation() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("service.log");
+    fs::write(&log, "one\ntwo\n")?;
+
+    let mut watched = process::Command::new("sleep").arg("1").spawn()?;
+    let tailr = process::Command::new(cargo_bin(PRG))
+        .args(["-f", "-s", "0.1", "--pid"])
+        .arg(watched.id().to_string())
+        .arg(&log)
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()?;
+
+    thread::sleep(Duration::from_millis(400));
+    fs::write(&log, "new\n")?;
+
+    watched.wait()?;
+    let output = tailr.wait_with_output()?;
+    assert_eq!(String::from_utf8(output.stdout)?, "one\ntwo\nnew\n");
+    assert!(String::from_utf8(output.stderr)?.contains("file truncated"));
+
+    Ok(())
+}
+
+#[test]
+fn follow_ignored_for_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("-f")
+        .write_stdin("a\nb\n")
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout("a\nb\n");
+
+    Ok(())
+}
+
+#[test]
+fn follow_ignored_for_fifo() -> TestResult {
+    // Unlike POSIX tail, which keeps reading a named FIFO, tailr reads it
+    // once and, with nothing else to follow, exits.
+    let dir = tempfile::tempdir()?;
+    let fifo = dir.path().join("fifo");
+    assert!(process::Command::new("mkfifo")
+        .arg(&fifo)
+        .status()?
+        .success());
+
+    let writer = {
+        let fifo = fifo.clone();
+        thread::spawn(move || fs::write(fifo, "x\ny\n"))
+    };
+    Command::cargo_bin(PRG)?
+        .args(["-f", "-n", "1"])
+        .arg(&fifo)
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout("y\n");
+    writer.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn follow_dies_no_files_remaining() -> TestResult {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["-f", &bad])
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no files remaining"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_pid() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "--pid", "0", TEN])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '0'"));
+
+    Ok(())
+}
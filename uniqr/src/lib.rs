@@ -1,7 +1,15 @@
 use clap::Parser;
+use regex::Regex;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::borrow::Cow;
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{self, BufRead, BufReader, Write};
+use std::num::NonZeroUsize;
 
 #[derive(Parser, Debug)]
 #[command(name = "headr")]
@@ -11,6 +19,120 @@ use std::io::{self, BufRead, BufReader, Write};
 pub struct Cli {
     #[arg(short = 'c', long = "count", help = "Show counts")]
     lines: bool,
+    #[arg(
+        short = 'd',
+        long = "repeated",
+        help = "Only print one line for each group of duplicates"
+    )]
+    repeated: bool,
+    #[arg(
+        short = 'D',
+        long = "all-repeated",
+        value_name = "METHOD",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "none",
+        conflicts_with = "lines",
+        help = "Print every line of each group of duplicates, delimiting groups by METHOD"
+    )]
+    all_repeated: Option<Delimit>,
+    #[arg(
+        long = "group",
+        value_name = "METHOD",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "separate",
+        conflicts_with_all = ["lines", "repeated", "all_repeated", "unique", "global"],
+        help = "Print every line, with a blank line around each group as set by METHOD"
+    )]
+    group: Option<GroupMode>,
+    #[arg(
+        short = 'u',
+        long = "unique",
+        help = "Only print lines that are not repeated"
+    )]
+    unique: bool,
+    #[arg(
+        short = 'f',
+        long = "skip-fields",
+        value_name = "N",
+        default_value = "0",
+        help = "Avoid comparing the first N fields"
+    )]
+    skip_fields: usize,
+    #[arg(
+        short = 's',
+        long = "skip-chars",
+        value_name = "N",
+        default_value = "0",
+        help = "Avoid comparing the first N characters"
+    )]
+    skip_chars: usize,
+    #[arg(
+        short = 'w',
+        long = "check-chars",
+        value_name = "N",
+        help = "Compare no more than N characters"
+    )]
+    check_chars: Option<usize>,
+    #[arg(
+        short = 'i',
+        long = "ignore-case",
+        help = "Ignore differences in case when comparing"
+    )]
+    ignore_case: bool,
+    #[arg(
+        long = "key",
+        value_name = "FIELD[,DELIM]",
+        value_parser = parse_key,
+        help = "Compare only field FIELD, split on DELIM or else on blanks"
+    )]
+    key: Option<KeyField>,
+    #[arg(
+        long = "key-regex",
+        value_name = "REGEX",
+        conflicts_with = "key",
+        value_parser = parse_regex,
+        help = "Compare only the first match of REGEX, or its first capture group"
+    )]
+    key_regex: Option<Regex>,
+    #[arg(
+        long = "global",
+        conflicts_with = "all_repeated",
+        help = "Remove duplicates anywhere in the input, keeping first-seen order"
+    )]
+    global: bool,
+    #[arg(
+        long = "max-memory",
+        value_name = "SIZE",
+        requires = "global",
+        conflicts_with_all = ["lines", "repeated", "unique"],
+        value_parser = parse_size,
+        help = "Keep only hashes of seen lines once they use more than SIZE bytes (K, M, G suffixes); not with -c, -d or -u, which must keep every line"
+    )]
+    max_memory: Option<usize>,
+    #[arg(
+        long = "sort-count",
+        conflicts_with_all = ["repeated", "all_repeated", "unique", "group", "global"],
+        help = "Count every distinct line, adjacent or not, and print them by descending count"
+    )]
+    sort_count: bool,
+    #[arg(
+        long = "top",
+        value_name = "N",
+        conflicts_with_all = ["repeated", "all_repeated", "unique", "group", "global"],
+        help = "Like --sort-count, but print only the N most frequent lines, in bounded memory"
+    )]
+    top: Option<NonZeroUsize>,
+    #[arg(
+        long = "format",
+        value_enum,
+        default_value = "text",
+        help = "Output format for -c, --sort-count and --top"
+    )]
+    format: Format,
     #[arg(help = "Input files", default_value = "-")]
     ifile: String,
     #[arg(help = "Output file")]
@@ -19,49 +141,551 @@ pub struct Cli {
 
 pub type MyResult<T> = Result<T, Box<dyn Error>>;
 
+#[derive(clap::ValueEnum, Debug, Eq, PartialEq, Clone, Copy)]
+enum Format {
+    Text,
+    /// One JSON object per line
+    Json,
+    Csv,
+}
+
+/// A counted line for the structured formats.
+struct Record<'a> {
+    line: &'a str,
+    count: u64,
+    first_line_no: u64,
+}
+
+impl Record<'_> {
+    /// Field names in order, which are also the CSV header.
+    const FIELDS: [&'static str; 3] = ["line", "count", "first_line_no"];
+}
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let [line, count, first_line_no] = Self::FIELDS;
+        let mut record = serializer.serialize_struct("Record", Self::FIELDS.len())?;
+        record.serialize_field(line, self.line)?;
+        record.serialize_field(count, &self.count)?;
+        record.serialize_field(first_line_no, &self.first_line_no)?;
+        record.end()
+    }
+}
+
+/// Where counted lines go, set up once for the chosen format. CSV goes
+/// through a single writer, with the header written up front so that even
+/// empty output has one.
+enum Output {
+    Text(Box<dyn Write>),
+    Json(Box<dyn Write>),
+    Csv(Box<csv::Writer<Box<dyn Write>>>),
+}
+
+impl Output {
+    fn new(format: Format, out_file: Box<dyn Write>) -> MyResult<Self> {
+        Ok(match format {
+            Format::Text => Output::Text(out_file),
+            Format::Json => Output::Json(out_file),
+            Format::Csv => {
+                let mut table = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(out_file);
+                table.write_record(Record::FIELDS)?;
+                Output::Csv(Box::new(table))
+            }
+        })
+    }
+
+    /// Writes one counted line, as `text` in the text format and as
+    /// `record` otherwise.
+    fn print(&mut self, record: &Record, text: fmt::Arguments) -> MyResult<()> {
+        match self {
+            Output::Text(out_file) => out_file.write_fmt(text)?,
+            Output::Json(out_file) => {
+                serde_json::to_writer(&mut *out_file, record)?;
+                writeln!(out_file)?;
+            }
+            Output::Csv(table) => table.serialize(record)?,
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> MyResult<()> {
+        match self {
+            Output::Text(out_file) | Output::Json(out_file) => out_file.flush()?,
+            Output::Csv(table) => table.flush()?,
+        }
+        Ok(())
+    }
+}
+
+/// Where `--all-repeated` puts blank lines.
+#[derive(clap::ValueEnum, Debug, Eq, PartialEq, Clone, Copy)]
+enum Delimit {
+    None,
+    /// Before each group
+    Prepend,
+    /// Between groups
+    Separate,
+}
+
+/// Where `--group` puts blank lines.
+#[derive(clap::ValueEnum, Debug, Eq, PartialEq, Clone, Copy)]
+enum GroupMode {
+    /// Between groups
+    Separate,
+    /// Before each group
+    Prepend,
+    /// After each group
+    Append,
+    /// Before and after each group
+    Both,
+}
+
+/// The field compared with `--key`.
+#[derive(Debug, Clone, PartialEq)]
+struct KeyField {
+    /// 1-based field number.
+    field: usize,
+    /// Field separator; runs of blanks if unset.
+    delim: Option<String>,
+}
+
+/// Parses `FIELD[,DELIM]`. Everything after the first comma is the
+/// delimiter, so `2,,` splits on commas.
+fn parse_key(s: &str) -> Result<KeyField, String> {
+    let (field, delim) = match s.split_once(',') {
+        Some((field, delim)) => (field, Some(delim)),
+        None => (s, None),
+    };
+    let field = field
+        .parse::<usize>()
+        .ok()
+        .filter(|&n| n > 0)
+        .ok_or_else(|| format!("Invalid key field: {}", s))?;
+    if delim == Some("") {
+        return Err(format!("Invalid key delimiter: {}", s));
+    }
+    Ok(KeyField {
+        field,
+        delim: delim.map(str::to_string),
+    })
+}
+
+fn parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|_| format!("Invalid regex \"{}\"", s))
+}
+
+/// Parses a byte count with an optional binary K, M or G suffix.
+fn parse_size(s: &str) -> Result<usize, String> {
+    let (digits, unit) = match s.char_indices().last() {
+        Some((i, 'K')) => (&s[..i], 1 << 10),
+        Some((i, 'M')) => (&s[..i], 1 << 20),
+        Some((i, 'G')) => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| format!("Invalid size: {}", s))
+}
+
 pub fn parse_args() -> MyResult<Cli> {
     let args = Cli::parse();
     Ok(args)
 }
 
 pub fn run(args: Cli) -> MyResult<()> {
-    let mut file = open(&args.ifile).map_err(|e| format!("{}: {}", args.ifile, e))?;
+    if args.format != Format::Text && !(args.lines || args.sort_count || args.top.is_some()) {
+        return Err(From::from("--format needs -c, --sort-count or --top"));
+    }
+    let file = open(&args.ifile).map_err(|e| format!("{}: {}", args.ifile, e))?;
 
     let mut out_file: Box<dyn Write> = match &args.ofile {
         Some(out_name) => Box::new(File::create(out_name)?),
         _ => Box::new(io::stdout()),
     };
+    // -D, --group and plain --global print input lines as they are, so they
+    // take the file itself; --format needs options that rule them out.
+    if args.all_repeated.is_some() || args.group.is_some() {
+        print_runs(&args, file, &mut out_file)?;
+    } else if args.global && !(args.lines || args.repeated || args.unique) {
+        print_first_seen(&args, file, &mut out_file)?;
+    } else {
+        let mut out = Output::new(args.format, out_file)?;
+        if args.global {
+            dedupe_global(&args, file, &mut out)?;
+        } else if args.sort_count || args.top.is_some() {
+            print_ranked(&args, file, &mut out)?;
+        } else {
+            dedupe_adjacent(&args, file, &mut out)?;
+        }
+        return out.flush();
+    }
+    out_file.flush()?;
+    Ok(())
+}
 
-    let mut print = |count: u64, text: &str| -> MyResult<()> {
-        if count > 0 {
-            if args.lines {
-                write!(out_file, "{:>4} {}", count, text)?;
-            } else {
-                write!(out_file, "{}", text)?;
-            }
+/// Prints a group of `count` lines if the options select it. With -d and
+/// -u together that is none of them, as in GNU uniq.
+fn print_group(args: &Cli, out: &mut Output, count: u64, text: &str, line_no: u64) -> MyResult<()> {
+    let selected = count > 0 && (count > 1 || !args.repeated) && (count == 1 || !args.unique);
+    if selected {
+        let record = Record {
+            line: strip_newline(text),
+            count,
+            first_line_no: line_no,
         };
-        Ok(())
-    };
+        if args.lines {
+            out.print(&record, format_args!("{:>4} {}", count, text))?;
+        } else {
+            out.print(&record, format_args!("{}", text))?;
+        }
+    }
+    Ok(())
+}
+
+/// Collapses runs of adjacent lines with the same key, printing each run
+/// once it ends.
+fn dedupe_adjacent(args: &Cli, mut file: impl BufRead, out: &mut Output) -> MyResult<()> {
     let mut text = String::new();
     let mut pre_match = String::new();
     let mut count = 0;
-    loop {
-        let bytes = file.read_line(&mut text)?;
-        if bytes == 0 {
-            break;
+    let mut line_no = 0;
+    let mut first_line_no = 0;
+    while file.read_line(&mut text)? > 0 {
+        line_no += 1;
+        if count == 0 || key(&text, args) != key(&pre_match, args) {
+            print_group(args, out, count, &pre_match, first_line_no)?;
+            pre_match = text.to_string();
+            first_line_no = line_no;
+            count = 0;
         }
-        if text.trim_end() != pre_match.trim_end() {
-            print(count, &pre_match)?;
+        count += 1;
+        text.clear();
+    }
+    print_group(args, out, count, &pre_match, first_line_no)?;
+    Ok(())
+}
+
+/// Prints the lines of runs as they are read: every line for --group, with
+/// blank lines around runs, and only repeated runs for -D.
+fn print_runs(args: &Cli, mut file: impl BufRead, out_file: &mut dyn Write) -> MyResult<()> {
+    let mut text = String::new();
+    let mut pre_match = String::new();
+    let mut count = 0;
+    // Runs written so far, to place the blank lines.
+    let mut shown = 0;
+    while file.read_line(&mut text)? > 0 {
+        if count == 0 || key(&text, args) != key(&pre_match, args) {
             pre_match = text.to_string();
             count = 0;
+            if let Some(mode) = args.group {
+                if shown > 0 || matches!(mode, GroupMode::Prepend | GroupMode::Both) {
+                    writeln!(out_file)?;
+                }
+                shown += 1;
+            }
         }
         count += 1;
+        if args.group.is_some() {
+            write!(out_file, "{}", text)?;
+        }
+        // -D prints each duplicate as it is read. Like GNU uniq, adding -u
+        // leaves out the first line of each group.
+        if let Some(method) = args.all_repeated.filter(|_| count > 1) {
+            if count == 2 {
+                if method == Delimit::Prepend || method == Delimit::Separate && shown > 0 {
+                    writeln!(out_file)?;
+                }
+                shown += 1;
+                if !args.unique {
+                    write!(out_file, "{}", pre_match)?;
+                }
+            }
+            write!(out_file, "{}", text)?;
+        }
         text.clear();
     }
-    print(count, &pre_match)?;
+    if shown > 0 && matches!(args.group, Some(GroupMode::Append | GroupMode::Both)) {
+        writeln!(out_file)?;
+    }
     Ok(())
 }
 
+/// Prints the first line for each distinct key in the whole input as soon
+/// as it is seen. Only this needs no counts, so only this honours
+/// `--max-memory`.
+fn print_first_seen(args: &Cli, mut file: impl BufRead, out_file: &mut dyn Write) -> MyResult<()> {
+    let mut index = KeyIndex::new(args.max_memory);
+    let mut text = String::new();
+    let mut seen = 0;
+    while file.read_line(&mut text)? > 0 {
+        if index.get_or_insert(&key(&text, args), seen).1 {
+            write!(out_file, "{}", text)?;
+            seen += 1;
+        }
+        text.clear();
+    }
+    Ok(())
+}
+
+/// Counts each distinct key in the whole input for -c, -d and -u, then
+/// prints the groups in first-seen order. This holds every distinct line.
+fn dedupe_global(args: &Cli, mut file: impl BufRead, out: &mut Output) -> MyResult<()> {
+    let mut index = KeyIndex::new(None);
+    // First line, count and first line number of each key.
+    let mut groups: Vec<(String, u64, u64)> = Vec::new();
+    let mut text = String::new();
+    let mut line_no = 0;
+    while file.read_line(&mut text)? > 0 {
+        line_no += 1;
+        match index.get_or_insert(&key(&text, args), groups.len()) {
+            (_, true) => groups.push((text.clone(), 1, line_no)),
+            (i, false) => groups[i].1 += 1,
+        }
+        text.clear();
+    }
+    for (line, count, line_no) in groups {
+        print_group(args, out, count, &line, line_no)?;
+    }
+    Ok(())
+}
+
+/// Prints distinct lines by descending count, ties in first-seen order,
+/// each with its count and share of all lines.
+fn print_ranked(args: &Cli, mut file: impl BufRead, out: &mut Output) -> MyResult<()> {
+    // Enough slots that anything in the top N is very likely exact.
+    let capacity = args.top.map(|n| n.get().saturating_mul(100).max(10_000));
+    let mut tally = Tally::new(capacity);
+    let mut text = String::new();
+    while file.read_line(&mut text)? > 0 {
+        tally.add(key(&text, args), &text);
+        text.clear();
+    }
+    if let (true, Some(capacity)) = (tally.approximate, capacity) {
+        eprintln!(
+            "more than {} distinct lines; counts are upper bounds",
+            capacity
+        );
+    }
+    let limit = args.top.map_or(usize::MAX, |n| n.get());
+    for group in tally.ranked().into_iter().take(limit) {
+        let record = Record {
+            line: strip_newline(&group.line),
+            count: group.count,
+            first_line_no: group.line_no,
+        };
+        out.print(
+            &record,
+            format_args!(
+                "{:>4} {:>5.1}% {}\n",
+                group.count,
+                group.count as f64 * 100.0 / tally.total as f64,
+                record.line
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// One distinct key with the first line that had it.
+struct Group {
+    key: String,
+    line: String,
+    count: u64,
+    /// 1-based input line number of `line`.
+    line_no: u64,
+}
+
+/// Counts lines by key. With a capacity this is the Space-Saving algorithm:
+/// once full, a new key takes over the slot of the least frequent one and
+/// inherits its count plus one. Memory stays bounded, every key seen more
+/// than total/capacity times keeps a slot, and counts are only ever
+/// overestimated.
+struct Tally {
+    capacity: Option<usize>,
+    groups: Vec<Group>,
+    index: HashMap<String, usize>,
+    /// `(count, slot)` for every group, kept only with a capacity.
+    by_count: BTreeSet<(u64, usize)>,
+    total: u64,
+    /// Set once a slot has been taken over.
+    approximate: bool,
+}
+
+impl Tally {
+    fn new(capacity: Option<usize>) -> Self {
+        Tally {
+            capacity,
+            groups: Vec::new(),
+            index: HashMap::new(),
+            by_count: BTreeSet::new(),
+            total: 0,
+            approximate: false,
+        }
+    }
+
+    fn add(&mut self, key: Cow<str>, line: &str) {
+        self.total += 1;
+        let slot = match self.index.get(key.as_ref()) {
+            Some(&slot) => slot,
+            None => match self.capacity {
+                Some(capacity) if self.groups.len() >= capacity => {
+                    let &(_, slot) = self.by_count.first().expect("capacity is not zero");
+                    let group = &mut self.groups[slot];
+                    self.index.remove(&group.key);
+                    group.key = key.into_owned();
+                    group.line = line.to_string();
+                    group.line_no = self.total;
+                    self.index.insert(group.key.clone(), slot);
+                    self.approximate = true;
+                    slot
+                }
+                _ => {
+                    let slot = self.groups.len();
+                    self.index.insert(key.to_string(), slot);
+                    self.groups.push(Group {
+                        key: key.into_owned(),
+                        line: line.to_string(),
+                        count: 0,
+                        line_no: self.total,
+                    });
+                    slot
+                }
+            },
+        };
+        self.bump(slot);
+    }
+
+    fn bump(&mut self, slot: usize) {
+        let group = &mut self.groups[slot];
+        if self.capacity.is_some() {
+            self.by_count.remove(&(group.count, slot));
+            self.by_count.insert((group.count + 1, slot));
+        }
+        group.count += 1;
+    }
+
+    fn ranked(&self) -> Vec<&Group> {
+        let mut groups: Vec<_> = self.groups.iter().collect();
+        groups.sort_by(|a, b| b.count.cmp(&a.count).then(a.line_no.cmp(&b.line_no)));
+        groups
+    }
+}
+
+/// Maps each distinct key to the position it was first seen at. Holds the
+/// keys themselves until they take up more than `limit` bytes, then only
+/// 128-bit hashes of them, where a false match is vanishingly unlikely.
+struct KeyIndex {
+    keys: HashMap<String, usize>,
+    hashes: HashMap<u128, usize>,
+    used: usize,
+    limit: Option<usize>,
+}
+
+impl KeyIndex {
+    /// Rough bookkeeping cost of one stored key beyond its text.
+    const ENTRY_OVERHEAD: usize = 48;
+
+    fn new(limit: Option<usize>) -> Self {
+        KeyIndex {
+            keys: HashMap::new(),
+            hashes: HashMap::new(),
+            used: 0,
+            limit,
+        }
+    }
+
+    /// Returns the position stored for `key`, or stores `next` for it. The
+    /// flag is true if the key is new.
+    fn get_or_insert(&mut self, key: &str, next: usize) -> (usize, bool) {
+        if !self.hashes.is_empty() {
+            return match self.hashes.entry(hash_key(key)) {
+                Entry::Occupied(e) => (*e.get(), false),
+                Entry::Vacant(e) => (*e.insert(next), true),
+            };
+        }
+        if let Some(&i) = self.keys.get(key) {
+            return (i, false);
+        }
+        self.keys.insert(key.to_string(), next);
+        self.used += key.len() + Self::ENTRY_OVERHEAD;
+        if self.limit.is_some_and(|limit| self.used > limit) {
+            self.hashes = self.keys.drain().map(|(k, i)| (hash_key(&k), i)).collect();
+            self.keys.shrink_to_fit();
+        }
+        (next, true)
+    }
+}
+
+/// Two independent 64-bit hashes of `key`, combined.
+fn hash_key(key: &str) -> u128 {
+    let half = |seed: u8| {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        key.hash(&mut hasher);
+        hasher.finish() as u128
+    };
+    half(0) << 64 | half(1)
+}
+
+/// The part of `line` that is compared: trailing whitespace is ignored,
+/// --key or --key-regex pick out a key, then -f skips fields, -s skips
+/// characters and -w limits the length. A field for -f is a run of blanks
+/// followed by non-blanks, as in GNU uniq.
+fn key<'a>(line: &'a str, args: &Cli) -> Cow<'a, str> {
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    let mut rest = line.trim_end();
+    if let Some(key) = &args.key {
+        let field = match &key.delim {
+            Some(delim) => rest.split(delim.as_str()).nth(key.field - 1),
+            None => rest
+                .split(is_blank)
+                .filter(|f| !f.is_empty())
+                .nth(key.field - 1),
+        };
+        rest = field.unwrap_or("");
+    }
+    // Lines that do not match are compared whole.
+    if let Some(caps) = args.key_regex.as_ref().and_then(|re| re.captures(rest)) {
+        let group = if caps.len() > 1 {
+            caps.get(1)
+        } else {
+            caps.get(0)
+        };
+        rest = group.map_or("", |m| m.as_str());
+    }
+    for _ in 0..args.skip_fields {
+        rest = rest.trim_start_matches(is_blank);
+        rest = rest.trim_start_matches(|c| !is_blank(c));
+    }
+    rest = skip_chars(rest, args.skip_chars);
+    if let Some(n) = args.check_chars {
+        rest = &rest[..rest.len() - skip_chars(rest, n).len()];
+    }
+    if args.ignore_case {
+        Cow::Owned(rest.to_lowercase())
+    } else {
+        Cow::Borrowed(rest)
+    }
+}
+
+/// `line` without its line terminator.
+fn strip_newline(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// `s` without its first `n` characters.
+fn skip_chars(s: &str, n: usize) -> &str {
+    match s.char_indices().nth(n) {
+        Some((i, _)) => &s[i..],
+        None => "",
+    }
+}
+
 fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     match filename {
         "-" => Ok(Box::new(BufReader::new(io::stdin()))),
@@ -604,3 +604,423 @@ fn t6_outfile_count() -> Result<()> {
 fn t6_stdin_outfile_count() -> Result<()> {
     run_stdin_outfile_count(&T6)
 }
+
+// --------------------------------------------------
+fn run_args(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read_to_string(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn three_repeated() -> Result<()> {
+    run_args(&["-d", THREE.input], "tests/expected/three.txt.d.out")
+}
+
+#[test]
+fn three_all_repeated() -> Result<()> {
+    run_args(&["-D", THREE.input], "tests/expected/three.txt.D.out")
+}
+
+#[test]
+fn three_unique() -> Result<()> {
+    run_args(&["--unique", THREE.input], "tests/expected/three.txt.u.out")
+}
+
+#[test]
+fn three_count_repeated() -> Result<()> {
+    run_args(
+        &["-c", "--repeated", THREE.input],
+        "tests/expected/three.txt.c.d.out",
+    )
+}
+
+#[test]
+fn three_count_unique() -> Result<()> {
+    run_args(
+        &["-c", "-u", THREE.input],
+        "tests/expected/three.txt.c.u.out",
+    )
+}
+
+#[test]
+fn three_repeated_unique() -> Result<()> {
+    run_args(
+        &["-d", "-u", THREE.input],
+        "tests/expected/three.txt.d.u.out",
+    )
+}
+
+#[test]
+fn three_all_repeated_unique() -> Result<()> {
+    run_args(
+        &["-D", "-u", THREE.input],
+        "tests/expected/three.txt.D.u.out",
+    )
+}
+
+#[test]
+fn dies_count_all_repeated() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "-D", THREE.input])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+const LOG: &str = "tests/inputs/log.txt";
+
+#[test]
+fn log_skip_fields() -> Result<()> {
+    run_args(&["-f", "2", LOG], "tests/expected/log.txt.f2.out")
+}
+
+#[test]
+fn log_count_skip_fields_ignore_case() -> Result<()> {
+    run_args(
+        &["-c", "--skip-fields", "2", "-i", LOG],
+        "tests/expected/log.txt.c.f2.i.out",
+    )
+}
+
+#[test]
+fn log_skip_chars_check_chars() -> Result<()> {
+    run_args(
+        &["-s", "11", "--check-chars", "3", LOG],
+        "tests/expected/log.txt.s11.w3.out",
+    )
+}
+
+#[test]
+fn log_skip_all_fields() -> Result<()> {
+    run_args(&["-f", "10", LOG], "tests/expected/log.txt.f10.out")
+}
+
+#[test]
+fn blank_first_line_count() -> Result<()> {
+    run_args(
+        &["-c", "tests/inputs/blank.txt"],
+        "tests/expected/blank.txt.c.out",
+    )
+}
+
+#[test]
+fn dies_bad_skip_fields() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "x", LOG])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'x'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn three_global() -> Result<()> {
+    run_args(
+        &["--global", THREE.input],
+        "tests/expected/three.txt.global.out",
+    )
+}
+
+#[test]
+fn three_global_count() -> Result<()> {
+    run_args(
+        &["--global", "-c", THREE.input],
+        "tests/expected/three.txt.global.c.out",
+    )
+}
+
+#[test]
+fn log_global_unique() -> Result<()> {
+    run_args(
+        &["--global", "-u", "-f", "2", "-i", LOG],
+        "tests/expected/log.txt.global.u.f2.i.out",
+    )
+}
+
+#[test]
+fn global_max_memory_hashes_keys() -> Result<()> {
+    // Far more distinct lines than fit in 1K, each repeated out of order.
+    let input: String = (0..3000).map(|i| format!("line {}\n", i % 1000)).collect();
+    let expected: String = (0..1000).map(|i| format!("line {}\n", i)).collect();
+    Command::cargo_bin(PRG)?
+        .args(["--global", "--max-memory", "1K"])
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+#[test]
+fn dies_max_memory_with_counts() -> Result<()> {
+    for flag in ["-c", "-d", "-u"] {
+        Command::cargo_bin(PRG)?
+            .args(["--global", "--max-memory", "1K", flag, THREE.input])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+    Ok(())
+}
+
+#[test]
+fn dies_bad_max_memory() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--global", "--max-memory", "lots", THREE.input])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid size: lots"));
+    Ok(())
+}
+
+#[test]
+fn dies_global_all_repeated() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--global", "-D", THREE.input])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn three_group() -> Result<()> {
+    run_args(
+        &["--group", THREE.input],
+        "tests/expected/three.txt.group.out",
+    )
+}
+
+#[test]
+fn three_group_both() -> Result<()> {
+    run_args(
+        &["--group=both", THREE.input],
+        "tests/expected/three.txt.group.both.out",
+    )
+}
+
+#[test]
+fn three_all_repeated_separate() -> Result<()> {
+    run_args(
+        &["--all-repeated=separate", THREE.input],
+        "tests/expected/three.txt.D.separate.out",
+    )
+}
+
+#[test]
+fn three_all_repeated_prepend() -> Result<()> {
+    run_args(
+        &["--all-repeated=prepend", THREE.input],
+        "tests/expected/three.txt.D.prepend.out",
+    )
+}
+
+#[test]
+fn dies_group_count() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--group", "-c", THREE.input])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn dies_bad_group_method() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--group=around", THREE.input])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'around'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn three_sort_count() -> Result<()> {
+    run_args(
+        &["--sort-count", THREE.input],
+        "tests/expected/three.txt.sort-count.out",
+    )
+}
+
+#[test]
+fn log_top_skip_fields_ignore_case() -> Result<()> {
+    run_args(
+        &["--top", "2", "-f", "2", "-i", LOG],
+        "tests/expected/log.txt.top2.f2.i.out",
+    )
+}
+
+#[test]
+fn top_many_distinct_lines() -> Result<()> {
+    // 30000 distinct lines overflow the tally, but "hot" stays exact.
+    let input: String = (0..30000).map(|i| format!("hot\ncold {}\n", i)).collect();
+    Command::cargo_bin(PRG)?
+        .args(["--top", "1"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("30000  50.0% hot\n")
+        .stderr("more than 10000 distinct lines; counts are upper bounds\n");
+    Ok(())
+}
+
+#[test]
+fn dies_zero_top() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--top", "0", THREE.input])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '0'"));
+    Ok(())
+}
+
+#[test]
+fn dies_top_repeated() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--top", "3", "-d", THREE.input])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+const ESCAPE: &str = "tests/inputs/escape.txt";
+
+#[test]
+fn escape_count_json() -> Result<()> {
+    run_args(
+        &["-c", "--format", "json", ESCAPE],
+        "tests/expected/escape.txt.c.json.out",
+    )
+}
+
+#[test]
+fn escape_count_csv() -> Result<()> {
+    run_args(
+        &["-c", "--format", "csv", ESCAPE],
+        "tests/expected/escape.txt.c.csv.out",
+    )
+}
+
+#[test]
+fn escape_count_json_outfile() -> Result<()> {
+    let outfile = NamedTempFile::new()?;
+    let outpath = &outfile.path().to_str().unwrap();
+    Command::cargo_bin(PRG)?
+        .args([ESCAPE, outpath, "-c", "--format", "json"])
+        .assert()
+        .success()
+        .stdout("");
+    let expected = fs::read_to_string("tests/expected/escape.txt.c.json.out")?;
+    assert_eq!(expected, fs::read_to_string(outpath)?);
+    Ok(())
+}
+
+#[test]
+fn three_sort_count_json() -> Result<()> {
+    run_args(
+        &["--sort-count", "--format", "json", THREE.input],
+        "tests/expected/three.txt.sort-count.json.out",
+    )
+}
+
+#[test]
+fn three_top_csv() -> Result<()> {
+    run_args(
+        &["--top", "2", "--format", "csv", THREE.input],
+        "tests/expected/three.txt.top2.csv.out",
+    )
+}
+
+#[test]
+fn csv_header_without_records() -> Result<()> {
+    for (args, input) in [(&["-c"][..], ""), (&["-c", "-d"][..], "a\nb\n")] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .args(["--format", "csv"])
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stdout("line,count,first_line_no\n");
+    }
+    Ok(())
+}
+
+#[test]
+fn dies_format_without_count() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--format", "json", THREE.input])
+        .assert()
+        .failure()
+        .stderr("--format needs -c, --sort-count or --top\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+const USERS: &str = "tests/inputs/users.csv";
+const REQUESTS: &str = "tests/inputs/requests.txt";
+
+#[test]
+fn users_key_field_delim() -> Result<()> {
+    run_args(
+        &["--key", "1,,", USERS],
+        "tests/expected/users.csv.key1.out",
+    )
+}
+
+#[test]
+fn users_global_count_key() -> Result<()> {
+    run_args(
+        &["--global", "-c", "--key", "1,,", USERS],
+        "tests/expected/users.csv.global.c.key1.out",
+    )
+}
+
+#[test]
+fn requests_count_key_blank_fields() -> Result<()> {
+    run_args(
+        &["-c", "--key", "1", REQUESTS],
+        "tests/expected/requests.txt.c.key1.out",
+    )
+}
+
+#[test]
+fn requests_count_key_regex() -> Result<()> {
+    run_args(
+        &["-c", "--key-regex", r"user=(\w+)", REQUESTS],
+        "tests/expected/requests.txt.c.key-regex.out",
+    )
+}
+
+#[test]
+fn dies_bad_key() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--key", "0", USERS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid key field: 0"));
+    Ok(())
+}
+
+#[test]
+fn dies_key_and_key_regex() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--key", "1", "--key-regex", "x", USERS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
@@ -1,7 +1,20 @@
 use clap::Parser;
+use glob::Pattern;
+use rayon::prelude::*;
+use regex::bytes::Regex;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::iter::Sum;
+use std::num::NonZeroUsize;
+use std::ops::Add;
+use std::path::Path;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+use walkdir::WalkDir;
 
 #[derive(Parser, Debug)]
 #[command(name = "headr")]
@@ -22,112 +35,926 @@ pub struct Cli {
         help = "Show character count"
     )]
     chars: bool,
+    #[arg(
+        short = 'L',
+        long = "max-line-length",
+        help = "Show the display width of the longest line"
+    )]
+    max_line_length: bool,
+    #[arg(
+        long = "longest-line-number",
+        help = "Show the line number of the longest line"
+    )]
+    longest_line: bool,
+    #[arg(long = "blank-lines", help = "Show the count of blank lines")]
+    blank_lines: bool,
+    #[arg(
+        long = "avg-line-length",
+        help = "Show the average display width of a line"
+    )]
+    avg_line_length: bool,
+    #[arg(
+        long = "format",
+        value_enum,
+        default_value = "text",
+        help = "Output format"
+    )]
+    format: Format,
+    #[arg(
+        long = "total",
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        help = "When to print a line with total counts"
+    )]
+    total: TotalMode,
+    #[arg(
+        long = "files0-from",
+        value_name = "F",
+        conflicts_with = "files",
+        help = "Read NUL-terminated input file names from F, or stdin if F is -"
+    )]
+    files0_from: Option<String>,
+    #[arg(
+        short = 'r',
+        long = "recursive",
+        help = "Count every regular file beneath directories"
+    )]
+    recursive: bool,
+    #[arg(
+        long = "include",
+        value_name = "GLOB",
+        requires = "recursive",
+        value_parser = parse_glob,
+        help = "Only count files found by --recursive whose name matches GLOB"
+    )]
+    include: Vec<Pattern>,
+    #[arg(
+        short = 'j',
+        long = "jobs",
+        value_name = "N",
+        help = "Count with N threads [default: one per CPU]"
+    )]
+    jobs: Option<NonZeroUsize>,
+    #[arg(
+        long = "stats",
+        help = "Also print a line length histogram, percentiles and the most frequent words"
+    )]
+    stats: bool,
+    #[arg(
+        long = "top",
+        value_name = "N",
+        default_value = "10",
+        requires = "stats",
+        help = "Number of words listed by --stats"
+    )]
+    top: usize,
+    #[arg(
+        long = "fold-case",
+        requires = "stats",
+        help = "Count words in --stats case-insensitively"
+    )]
+    fold_case: bool,
+    #[arg(
+        long = "stats-word-regex",
+        value_name = "REGEX",
+        requires = "stats",
+        value_parser = parse_regex,
+        help = "Pattern for the words counted by --stats, leaving -w alone [default: \\w+]"
+    )]
+    stats_word_regex: Option<Regex>,
+    #[arg(
+        long = "word-regex",
+        value_name = "REGEX",
+        value_parser = parse_regex,
+        help = "Count matches of REGEX within each line as words, also for --stats unless --stats-word-regex is given"
+    )]
+    word_regex: Option<Regex>,
+    #[arg(
+        long = "unicode-words",
+        conflicts_with = "word_regex",
+        help = "Count words between Unicode word boundaries"
+    )]
+    unicode_words: bool,
     #[arg(help = "Input files", default_value = "-")]
     files: Vec<String>,
 }
 
 pub type MyResult<T> = Result<T, Box<dyn Error>>;
 
-#[derive(Debug, PartialEq)]
-struct FileInfo {
-    lines: usize,
-    words: usize,
-    bytes: usize,
-    chars: usize,
+/// Regular files larger than this are split into chunks of about this size
+/// and the chunks counted in parallel.
+const CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Files counted per thread before a batch of results is printed. Enough
+/// that a slow file rarely leaves threads idle for long.
+const BATCH_PER_THREAD: usize = 8;
+
+fn parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|_| format!("Invalid regex \"{}\"", s))
+}
+
+fn parse_glob(s: &str) -> Result<Pattern, String> {
+    Pattern::new(s).map_err(|e| format!("invalid glob \"{}\": {}", s, e))
+}
+
+#[derive(clap::ValueEnum, Debug, Eq, PartialEq, Clone, Copy)]
+enum Format {
+    Text,
+    /// One object per line; the total has `"total": true` instead of a file
+    Json,
+    /// The total row has an empty file field
+    Csv,
+    /// The total row has an empty file field
+    Tsv,
+}
+
+#[derive(clap::ValueEnum, Debug, Eq, PartialEq, Clone, Copy)]
+enum TotalMode {
+    /// Only with more than one file
+    Auto,
+    Always,
+    /// Only the total, without per-file lines
+    Only,
+    Never,
+}
+
+/// A selectable output column, in display order.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum Column {
+    Lines,
+    Words,
+    Bytes,
+    Chars,
+    MaxLineLength,
+    LongestLine,
+    BlankLines,
+    AvgLineLength,
+}
+
+impl Column {
+    fn selected(args: &Cli) -> Vec<Column> {
+        [
+            (args.lines, Column::Lines),
+            (args.words, Column::Words),
+            (args.bytes, Column::Bytes),
+            (args.chars, Column::Chars),
+            (args.max_line_length, Column::MaxLineLength),
+            (args.longest_line, Column::LongestLine),
+            (args.blank_lines, Column::BlankLines),
+            (args.avg_line_length, Column::AvgLineLength),
+        ]
+        .into_iter()
+        .filter_map(|(on, column)| on.then_some(column))
+        .collect()
+    }
+
+    /// Field name used by the structured formats.
+    fn name(self) -> &'static str {
+        match self {
+            Column::Lines => "lines",
+            Column::Words => "words",
+            Column::Bytes => "bytes",
+            Column::Chars => "chars",
+            Column::MaxLineLength => "max_line_length",
+            Column::LongestLine => "longest_line",
+            Column::BlankLines => "blank_lines",
+            Column::AvgLineLength => "avg_line_length",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Field {
+    Count(usize),
+    Ratio(f64),
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::Count(n) => n.fmt(f),
+            Field::Ratio(r) => write!(f, "{:>1$.1}", r, f.width().unwrap_or(0)),
+        }
+    }
+}
+
+impl Serialize for Field {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Field::Count(n) => serializer.serialize_u64(*n as u64),
+            Field::Ratio(r) => serializer.serialize_f64(*r),
+        }
+    }
+}
+
+/// One file's counts as a named-field record for the structured formats.
+struct Record<'a> {
+    /// `None` for the total, so it can't be mistaken for a file named "total".
+    file: Option<&'a str>,
+    info: &'a FileInfo,
+    columns: &'a [Column],
+}
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len() + 1))?;
+        match self.file {
+            Some(file) => map.serialize_entry("file", file)?,
+            None => map.serialize_entry("total", &true)?,
+        }
+        for &column in self.columns {
+            map.serialize_entry(column.name(), &self.info.field(column))?;
+        }
+        map.end()
+    }
+}
+
+/// Writes one row per file in the chosen format. Text rows are held until
+/// `finish`, so the columns can be sized to the largest count printed.
+struct Printer {
+    format: Format,
+    columns: Vec<Column>,
+    rows: Vec<(String, FileInfo)>,
+    table: Option<csv::Writer<io::Stdout>>,
+}
+
+impl Printer {
+    fn new(args: &Cli) -> MyResult<Self> {
+        let columns = Column::selected(args);
+        let table = match args.format {
+            Format::Csv | Format::Tsv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .delimiter(if args.format == Format::Tsv {
+                        b'\t'
+                    } else {
+                        b','
+                    })
+                    .from_writer(io::stdout());
+                let header = std::iter::once("file").chain(columns.iter().map(|c| c.name()));
+                writer.write_record(header)?;
+                Some(writer)
+            }
+            _ => None,
+        };
+        Ok(Printer {
+            format: args.format,
+            columns,
+            rows: Vec::new(),
+            table,
+        })
+    }
+
+    /// Prints the total row. With `--total=only` the text format leaves out
+    /// the label, as GNU wc does. The structured formats mark it apart from
+    /// the file rows instead of naming it.
+    fn print_total(&mut self, info: &FileInfo, only: bool) -> MyResult<()> {
+        match self.format {
+            Format::Text if only => self.print(Some("-"), info),
+            Format::Text => self.print(Some("total"), info),
+            _ => self.print(None, info),
+        }
+    }
+
+    fn print(&mut self, name: Option<&str>, info: &FileInfo) -> MyResult<()> {
+        match (self.format, self.table.as_mut()) {
+            (Format::Json, _) => {
+                let record = Record {
+                    file: name,
+                    info,
+                    columns: &self.columns,
+                };
+                println!("{}", serde_json::to_string(&record)?);
+            }
+            (_, Some(table)) => {
+                let fields = self.columns.iter().map(|&c| info.field(c).to_string());
+                let name = name.unwrap_or_default().to_string();
+                table.write_record(std::iter::once(name).chain(fields))?;
+            }
+            _ => self
+                .rows
+                .push((name.unwrap_or_default().to_string(), *info)),
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> MyResult<()> {
+        if let Some(table) = self.table.as_mut() {
+            table.flush()?;
+        }
+        let width = self
+            .rows
+            .iter()
+            .flat_map(|(_, info)| {
+                self.columns
+                    .iter()
+                    .map(|&c| info.field(c).to_string().len())
+            })
+            .max()
+            .unwrap_or(1);
+        for (name, info) in &self.rows {
+            info.print_info(name, &self.columns, width);
+        }
+        Ok(())
+    }
+}
+
+/// Counts for one input, or the total of several when added together.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileInfo {
+    /// Newline characters.
+    pub lines: usize,
+    pub words: usize,
+    pub bytes: usize,
+    /// Characters in valid UTF-8 sequences.
+    pub chars: usize,
+    /// Display width of the widest line, with tabs expanded to multiples of 8.
+    pub max_line_length: usize,
+    /// 1-based number of the first line of width `max_line_length`.
+    pub longest_line: usize,
+    /// Lines holding nothing but whitespace.
+    pub blank_lines: usize,
+    /// Sum of the display widths of all lines.
+    pub line_width_total: usize,
+    /// Lines including a final one with no newline.
+    pub text_lines: usize,
+}
+
+impl Add for FileInfo {
+    type Output = FileInfo;
+
+    /// Totals two inputs. The longest line keeps its line number within
+    /// whichever input it came from.
+    fn add(mut self, other: FileInfo) -> FileInfo {
+        if other.max_line_length > self.max_line_length || self.longest_line == 0 {
+            self.max_line_length = other.max_line_length;
+            self.longest_line = other.longest_line;
+        }
+        self.lines += other.lines;
+        self.words += other.words;
+        self.bytes += other.bytes;
+        self.chars += other.chars;
+        self.blank_lines += other.blank_lines;
+        self.line_width_total += other.line_width_total;
+        self.text_lines += other.text_lines;
+        self
+    }
+}
+
+impl Sum for FileInfo {
+    fn sum<I: Iterator<Item = FileInfo>>(iter: I) -> FileInfo {
+        iter.fold(FileInfo::default(), Add::add)
+    }
 }
 
 impl FileInfo {
-    fn print_info(
-        &self,
-        name: String,
-        lines: bool,
-        words: bool,
-        bytes: bool,
-        chars: bool,
-    ) -> MyResult<()> {
-        let mut printstr: String = String::from("");
-        if lines {
-            printstr.push_str(&format!("{:8}", self.lines));
+    pub fn avg_line_length(&self) -> f64 {
+        if self.text_lines == 0 {
+            0.0
+        } else {
+            self.line_width_total as f64 / self.text_lines as f64
         }
-        if words {
-            printstr.push_str(&format!("{:8}", self.words));
+    }
+
+    /// Combines the counts of two consecutive pieces of one input, where
+    /// `next` starts at the beginning of a line.
+    fn append(mut self, next: FileInfo) -> FileInfo {
+        if next.max_line_length > self.max_line_length || self.longest_line == 0 {
+            self.max_line_length = next.max_line_length;
+            self.longest_line = match next.longest_line {
+                0 => 0,
+                n => self.text_lines + n,
+            };
         }
-        if bytes {
-            printstr.push_str(&format!("{:8}", self.bytes));
+        self.lines += next.lines;
+        self.words += next.words;
+        self.bytes += next.bytes;
+        self.chars += next.chars;
+        self.blank_lines += next.blank_lines;
+        self.line_width_total += next.line_width_total;
+        self.text_lines += next.text_lines;
+        self
+    }
+
+    fn field(&self, column: Column) -> Field {
+        match column {
+            Column::Lines => Field::Count(self.lines),
+            Column::Words => Field::Count(self.words),
+            Column::Bytes => Field::Count(self.bytes),
+            Column::Chars => Field::Count(self.chars),
+            Column::MaxLineLength => Field::Count(self.max_line_length),
+            Column::LongestLine => Field::Count(self.longest_line),
+            Column::BlankLines => Field::Count(self.blank_lines),
+            Column::AvgLineLength => Field::Ratio(self.avg_line_length()),
         }
-        if chars {
-            printstr.push_str(&format!("{:8}", self.chars));
+    }
+
+    fn print_info(&self, name: &str, columns: &[Column], width: usize) {
+        let mut printstr: String = String::from("");
+        for (i, &column) in columns.iter().enumerate() {
+            if i > 0 {
+                printstr.push(' ');
+            }
+            printstr.push_str(&format!("{:1$}", self.field(column), width));
         }
         if name != "-" {
             printstr.push_str(&format!(" {}", name));
         }
         println!("{}", printstr);
-        Ok(())
     }
 }
 
-fn count(mut file: impl BufRead) -> MyResult<FileInfo> {
-    let mut lines = 0;
-    let mut words: usize = 0;
-    let mut bytes: usize = 0;
-    let mut chars: usize = 0;
+/// Streaming counter over raw bytes, so input that is not valid UTF-8 can
+/// still be counted. Invalid bytes are not counted as characters but do
+/// belong to words, as with GNU wc.
+#[derive(Debug, Default)]
+struct Counter {
+    info: FileInfo,
+    in_word: bool,
+    line_started: bool,
+    line_has_content: bool,
+    line_pos: usize,
+    line_width: usize,
+    pending: [u8; 4],
+    pending_len: usize,
+    pending_need: usize,
+    /// Number of lines of each display width, kept only for `--stats`.
+    line_widths: Option<BTreeMap<usize, usize>>,
+}
 
-    let mut text = String::new();
-    loop {
-        match file.read_line(&mut text) {
-            Ok(0) => {
-                if text.trim().is_empty() {
-                    break;
-                } else {
-                    lines += 1;
+impl Counter {
+    fn update(&mut self, buf: &[u8]) {
+        self.info.bytes += buf.len();
+        for &byte in buf {
+            if self.pending_need > 0 {
+                if byte & 0b1100_0000 == 0b1000_0000 {
+                    self.pending[self.pending_len] = byte;
+                    self.pending_len += 1;
+                    if self.pending_len == self.pending_need {
+                        self.finish_char();
+                    }
+                    continue;
                 }
+                // Truncated sequence; the current byte starts afresh.
+                self.finish_char();
             }
-            Ok(bytes_read) => {
-                bytes += bytes_read;
-                lines += 1;
-                words += text.split_whitespace().count();
-                chars += text.chars().count();
+            self.line_started = true;
+            match byte {
+                0..=0x7f => {
+                    self.info.chars += 1;
+                    let is_space = matches!(byte, b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c);
+                    if is_space || byte.is_ascii_graphic() {
+                        self.visit(is_space);
+                    }
+                    match byte {
+                        b'\n' => {
+                            self.info.lines += 1;
+                            self.end_line();
+                        }
+                        b'\t' => self.advance(8 - self.line_pos % 8, true),
+                        // Carriage returns and form feeds move back to column 0.
+                        b'\r' | 0x0c => self.line_pos = 0,
+                        0x20..=0x7e => self.advance(1, is_space),
+                        _ => {}
+                    }
+                }
+                0xc2..=0xdf => self.start_char(byte, 2),
+                0xe0..=0xef => self.start_char(byte, 3),
+                0xf0..=0xf4 => self.start_char(byte, 4),
+                _ => self.advance(0, false),
             }
-            Err(error) => {
-                panic!("Error reading line: {}", error);
+        }
+    }
+
+    /// Moves the display position along the current line.
+    fn advance(&mut self, width: usize, is_space: bool) {
+        self.line_pos += width;
+        self.line_width = self.line_width.max(self.line_pos);
+        if !is_space {
+            self.line_has_content = true;
+        }
+    }
+
+    fn end_line(&mut self) {
+        let info = &mut self.info;
+        info.text_lines += 1;
+        if self.line_width > info.max_line_length || info.longest_line == 0 {
+            info.max_line_length = self.line_width;
+            info.longest_line = info.text_lines;
+        }
+        if let Some(line_widths) = self.line_widths.as_mut() {
+            *line_widths.entry(self.line_width).or_insert(0) += 1;
+        }
+        if !self.line_has_content {
+            info.blank_lines += 1;
+        }
+        info.line_width_total += self.line_width;
+        self.line_started = false;
+        self.line_has_content = false;
+        self.line_pos = 0;
+        self.line_width = 0;
+    }
+
+    fn start_char(&mut self, byte: u8, need: usize) {
+        self.pending[0] = byte;
+        self.pending_len = 1;
+        self.pending_need = need;
+    }
+
+    /// Decodes the pending multi-byte sequence, complete or not.
+    fn finish_char(&mut self) {
+        let decoded = std::str::from_utf8(&self.pending[..self.pending_len])
+            .ok()
+            .and_then(|s| s.chars().next());
+        match decoded {
+            Some(c) if self.pending_len == self.pending_need => {
+                self.info.chars += 1;
+                if !c.is_control() {
+                    self.visit(c.is_whitespace());
+                }
+                self.advance(c.width().unwrap_or(0), c.is_whitespace());
             }
+            _ => self.advance(0, false),
         }
-        text.clear();
+        self.pending_len = 0;
+        self.pending_need = 0;
     }
 
-    Ok(FileInfo {
-        lines,
-        words,
-        bytes,
-        chars,
-    })
+    /// Tracks whitespace-to-word transitions. Like GNU wc, control characters
+    /// and invalid bytes are never passed in, so they neither start nor end
+    /// a word.
+    fn visit(&mut self, is_space: bool) {
+        if is_space {
+            self.in_word = false;
+        } else if !self.in_word {
+            self.in_word = true;
+            self.info.words += 1;
+        }
+    }
+
+    /// Completes a trailing partial character and unterminated line.
+    fn flush(&mut self) {
+        if self.pending_need > 0 {
+            self.finish_char();
+        }
+        if self.line_started {
+            self.end_line();
+        }
+    }
+
+    fn finish(mut self) -> FileInfo {
+        self.flush();
+        self.info
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{count, FileInfo};
-    use std::io::Cursor;
+/// A word definition other than runs of non-whitespace. Words never span
+/// lines.
+#[derive(Debug, Clone)]
+enum WordMode {
+    /// Each match of a regex.
+    Regex(Regex),
+    /// Segments between Unicode word boundaries (UAX #29) holding a letter
+    /// or digit. Ideographs count as one word each.
+    Unicode,
+}
 
-    #[test]
-    fn test_count() {
-        let text = "I don't want the world. I just want your half.\r\n";
-        let info = count(Cursor::new(text));
-        assert!(info.is_ok());
-        let expects = FileInfo {
-            lines: 1,
-            words: 10,
-            chars: 48,
-            bytes: 48,
+impl WordMode {
+    fn for_each_word(&self, line: &[u8], mut f: impl FnMut(&str)) {
+        match self {
+            WordMode::Regex(re) => {
+                for word in re.find_iter(line) {
+                    f(&String::from_utf8_lossy(word.as_bytes()));
+                }
+            }
+            WordMode::Unicode => String::from_utf8_lossy(line).unicode_words().for_each(f),
+        }
+    }
+}
+
+/// How `--stats` picks out and groups words.
+struct StatsOptions {
+    words: WordMode,
+    fold_case: bool,
+}
+
+/// Counting options beyond what `Counter` does on its own.
+#[derive(Default)]
+struct CountOptions {
+    /// Replaces the whitespace word count.
+    words: Option<WordMode>,
+    stats: Option<StatsOptions>,
+}
+
+/// Line length and word frequency tables for `--stats`.
+#[derive(Debug, Default, PartialEq)]
+struct Stats {
+    /// Number of lines of each display width.
+    line_widths: BTreeMap<usize, usize>,
+    words: HashMap<String, usize>,
+}
+
+impl Stats {
+    fn add_word(&mut self, word: &str, fold_case: bool) {
+        let folded;
+        let word = if fold_case {
+            folded = word.to_lowercase();
+            &folded
+        } else {
+            word
         };
-        assert_eq!(info.unwrap(), expects);
+        match self.words.get_mut(word) {
+            Some(n) => *n += 1,
+            None => {
+                self.words.insert(word.to_string(), 1);
+            }
+        }
+    }
+
+    fn merge(mut self, other: Stats) -> Stats {
+        for (width, lines) in other.line_widths {
+            *self.line_widths.entry(width).or_insert(0) += lines;
+        }
+        for (word, n) in other.words {
+            *self.words.entry(word).or_insert(0) += n;
+        }
+        self
+    }
+
+    /// The `p`th percentile line width by the nearest-rank method.
+    fn percentile(&self, p: usize) -> usize {
+        let lines: usize = self.line_widths.values().sum();
+        let rank = (lines * p).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (&width, &n) in &self.line_widths {
+            seen += n;
+            if seen >= rank {
+                return width;
+            }
+        }
+        0
+    }
+
+    fn max_width(&self) -> usize {
+        self.line_widths.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// At most ten equal-width buckets of line widths, as
+    /// `(low, high, lines)`.
+    fn histogram(&self) -> Vec<(usize, usize, usize)> {
+        let step = (self.max_width() + 1).div_ceil(10);
+        let mut buckets: Vec<_> = (0..=self.max_width())
+            .step_by(step)
+            .map(|low| (low, low + step - 1, 0))
+            .collect();
+        for (&width, &n) in &self.line_widths {
+            buckets[width / step].2 += n;
+        }
+        buckets
+    }
+
+    /// The `n` most frequent words, ties broken alphabetically.
+    fn top_words(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut words: Vec<_> = self.words.iter().map(|(w, &c)| (w.as_str(), c)).collect();
+        words.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        words.truncate(n);
+        words
+    }
+
+    fn print_text(&self, top: usize) {
+        const BAR: usize = 40;
+        println!("line lengths:");
+        let histogram = self.histogram();
+        let most = histogram.iter().map(|b| b.2).max().unwrap_or(0).max(1);
+        for (low, high, lines) in histogram {
+            let row = format!(
+                "{:>12} {:>8} {}",
+                format!("{}-{}", low, high),
+                lines,
+                "#".repeat((lines * BAR).div_ceil(most))
+            );
+            println!("{}", row.trim_end());
+        }
+        println!(
+            "p50 {} p90 {} p99 {} max {}",
+            self.percentile(50),
+            self.percentile(90),
+            self.percentile(99),
+            self.max_width()
+        );
+        println!("top words:");
+        for (word, n) in self.top_words(top) {
+            println!("{:>8} {}", n, word);
+        }
+    }
+
+    fn print_json(&self, top: usize) -> MyResult<()> {
+        let histogram: Vec<_> = self
+            .histogram()
+            .into_iter()
+            .map(|(low, high, lines)| json!({"min": low, "max": high, "lines": lines}))
+            .collect();
+        let words: Vec<_> = self
+            .top_words(top)
+            .into_iter()
+            .map(|(word, n)| json!({"word": word, "count": n}))
+            .collect();
+        let report = json!({
+            "stats": {
+                "histogram": histogram,
+                "p50": self.percentile(50),
+                "p90": self.percentile(90),
+                "p99": self.percentile(99),
+                "max": self.max_width(),
+                "top_words": words,
+            }
+        });
+        println!("{}", serde_json::to_string(&report)?);
+        Ok(())
+    }
+}
+
+/// Counts `file` to the end, with words as runs of non-whitespace.
+pub fn count(mut file: impl BufRead) -> io::Result<FileInfo> {
+    let mut counter = Counter::default();
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        counter.update(buf);
+        let len = buf.len();
+        file.consume(len);
+    }
+    Ok(counter.finish())
+}
+
+/// Counts a line at a time, for word definitions that match within lines
+/// and to collect the `--stats` tables.
+fn count_lines(
+    mut file: impl BufRead,
+    opts: &CountOptions,
+) -> io::Result<(FileInfo, Option<Stats>)> {
+    let mut counter = Counter {
+        line_widths: opts.stats.as_ref().map(|_| BTreeMap::new()),
+        ..Default::default()
+    };
+    let mut stats = opts.stats.as_ref().map(|_| Stats::default());
+    let mut words = 0;
+    let mut line = Vec::new();
+    while file.read_until(b'\n', &mut line)? > 0 {
+        counter.update(&line);
+        if let Some(mode) = &opts.words {
+            mode.for_each_word(&line, |_| words += 1);
+        }
+        if let (Some(stats_opts), Some(stats)) = (&opts.stats, stats.as_mut()) {
+            stats_opts
+                .words
+                .for_each_word(&line, |word| stats.add_word(word, stats_opts.fold_case));
+        }
+        line.clear();
+    }
+    counter.flush();
+    if opts.words.is_some() {
+        counter.info.words = words;
+    }
+    if let Some(stats) = stats.as_mut() {
+        stats.line_widths = counter.line_widths.take().unwrap_or_default();
+    }
+    Ok((counter.info, stats))
+}
+
+/// Counts with the fast byte counter unless `opts` needs whole lines.
+fn count_input(file: impl BufRead, opts: &CountOptions) -> io::Result<(FileInfo, Option<Stats>)> {
+    match opts {
+        CountOptions {
+            words: None,
+            stats: None,
+        } => count(file).map(|info| (info, None)),
+        _ => count_lines(file, opts),
+    }
+}
+
+/// Counts one input, splitting large regular files into chunks. The error
+/// is the message to report.
+fn count_file(filename: &str, opts: &CountOptions) -> Result<(FileInfo, Option<Stats>), String> {
+    let read_error = |err| format!("failed to read {}: {}", filename, err);
+    if filename == "-" {
+        return count_input(BufReader::new(io::stdin()), opts).map_err(read_error);
+    }
+    let file =
+        File::open(filename).map_err(|err| format!("failed to open {}: {}\n", filename, err))?;
+    count_open_file(file, Path::new(filename), opts).map_err(read_error)
+}
+
+/// Counts the file at `path` as wcr does by default. Large regular files are
+/// split into chunks counted in parallel on the global thread pool.
+pub fn count_path(path: impl AsRef<Path>) -> io::Result<FileInfo> {
+    let path = path.as_ref();
+    count_open_file(File::open(path)?, path, &CountOptions::default()).map(|(info, _)| info)
+}
+
+/// Counts `file`, opened from `path`, in chunks if it is a large regular file.
+fn count_open_file(
+    file: File,
+    path: &Path,
+    opts: &CountOptions,
+) -> io::Result<(FileInfo, Option<Stats>)> {
+    let meta = file.metadata()?;
+    if meta.is_file() && meta.len() > CHUNK_SIZE {
+        count_chunked(path, meta.len(), CHUNK_SIZE, |chunk| {
+            count_input(chunk, opts)
+        })
+        .map(join_chunks)
+    } else {
+        count_input(BufReader::new(file), opts)
+    }
+}
+
+/// Joins the results for consecutive chunks of one file.
+fn join_chunks(chunks: Vec<(FileInfo, Option<Stats>)>) -> (FileInfo, Option<Stats>) {
+    chunks.into_iter().fold(
+        (FileInfo::default(), None),
+        |(info, stats), (next_info, next_stats)| {
+            let stats = match (stats, next_stats) {
+                (Some(stats), Some(next)) => Some(stats.merge(next)),
+                (stats, next) => stats.or(next),
+            };
+            (info.append(next_info), stats)
+        },
+    )
+}
+
+/// Runs `count` over the first `len` bytes of a regular file in chunks on
+/// the thread pool, returning the result for each chunk in order.
+fn count_chunked<T, F>(path: &Path, len: u64, chunk_size: u64, count: F) -> io::Result<Vec<T>>
+where
+    T: Send,
+    F: Fn(BufReader<io::Take<File>>) -> io::Result<T> + Sync,
+{
+    let bounds = chunk_bounds(File::open(path)?, len, chunk_size)?;
+    bounds
+        .par_windows(2)
+        .map(|range| {
+            let mut file = File::open(path)?;
+            file.seek(SeekFrom::Start(range[0]))?;
+            count(BufReader::new(file.take(range[1] - range[0])))
+        })
+        .collect()
+}
+
+/// Picks chunk offsets about `chunk_size` apart, each moved forward to just
+/// past a newline. Every chunk then starts at column 0, outside any word or
+/// multi-byte character, so the counts of consecutive chunks simply add up.
+/// A file without newlines stays a single chunk.
+fn chunk_bounds(mut file: File, len: u64, chunk_size: u64) -> io::Result<Vec<u64>> {
+    let mut bounds = vec![0];
+    let mut target = chunk_size;
+    while target < len {
+        file.seek(SeekFrom::Start(target))?;
+        match next_line_start(BufReader::new(&mut file))? {
+            Some(skipped) if target + skipped < len => {
+                bounds.push(target + skipped);
+                target += skipped + chunk_size;
+            }
+            _ => break,
+        }
+    }
+    bounds.push(len);
+    Ok(bounds)
+}
+
+/// Returns how many bytes to read to get just past the next newline.
+fn next_line_start(mut file: impl BufRead) -> io::Result<Option<u64>> {
+    let mut skipped = 0;
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        if let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+            return Ok(Some(skipped + pos as u64 + 1));
+        }
+        let len = buf.len();
+        skipped += len as u64;
+        file.consume(len);
     }
 }
 
 pub fn parse_args() -> Cli {
     let mut args = Cli::parse();
-    if [args.lines, args.words, args.bytes, args.chars]
-        .iter()
-        .all(|v| !v)
+    if [
+        args.lines,
+        args.words,
+        args.bytes,
+        args.chars,
+        args.max_line_length,
+        args.longest_line,
+        args.blank_lines,
+        args.avg_line_length,
+    ]
+    .iter()
+    .all(|v| !v)
     {
         args.lines = true;
         args.words = true;
@@ -136,41 +963,157 @@ pub fn parse_args() -> Cli {
     args
 }
 
+/// Lists the files to count: the command-line names or those read with
+/// `--files0-from`, with directories expanded under `--recursive`.
+fn input_files(args: &Cli) -> MyResult<Vec<String>> {
+    let names = match &args.files0_from {
+        Some(source) => read_files0(source)?,
+        None => args.files.clone(),
+    };
+    if !args.recursive {
+        return Ok(names);
+    }
+    let mut files = Vec::with_capacity(names.len());
+    for name in names {
+        if name != "-" && Path::new(&name).is_dir() {
+            walk_dir(&name, &args.include, &mut files);
+        } else {
+            files.push(name);
+        }
+    }
+    Ok(files)
+}
+
+/// Reads a NUL-separated list of file names, as written by `find -print0`.
+fn read_files0(source: &str) -> MyResult<Vec<String>> {
+    let mut buf = Vec::new();
+    open(source)
+        .map_err(|e| format!("cannot open {} for reading: {}", source, e))?
+        .read_to_end(&mut buf)?;
+    let mut entries: Vec<&[u8]> = buf.split(|&b| b == 0).collect();
+    // The list normally ends with a NUL, leaving an empty final entry.
+    if entries.last().is_some_and(|name| name.is_empty()) {
+        entries.pop();
+    }
+    let mut names = Vec::new();
+    for (i, name) in entries.into_iter().enumerate() {
+        if name.is_empty() {
+            eprintln!("{}:{}: invalid zero-length file name", source, i + 1);
+            continue;
+        }
+        match String::from_utf8(name.to_vec()) {
+            Ok(name) if name == "-" && source == "-" => {
+                eprintln!("when reading file names from stdin, no file name of '-' allowed")
+            }
+            Ok(name) => names.push(name),
+            Err(_) => eprintln!(
+                "{}:{}: invalid file name {:?}",
+                source,
+                i + 1,
+                String::from_utf8_lossy(name)
+            ),
+        }
+    }
+    Ok(names)
+}
+
+/// Appends every regular file beneath `dir` in sorted order, keeping only
+/// names that match one of `include` when any are given.
+fn walk_dir(dir: &str, include: &[Pattern], files: &mut Vec<String>) {
+    let entries = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| match e {
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+            Ok(entry) => Some(entry),
+        })
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            include.is_empty() || include.iter().any(|glob| glob.matches(&name))
+        });
+    files.extend(entries.map(|entry| entry.path().display().to_string()));
+}
+
 pub fn run(args: Cli) -> MyResult<()> {
-    let mut total: FileInfo = FileInfo {
-        lines: 0,
-        words: 0,
-        bytes: 0,
-        chars: 0,
+    if args.stats && matches!(args.format, Format::Csv | Format::Tsv) {
+        return Err(From::from(
+            "--stats only supports the text and json formats",
+        ));
+    }
+    let words = match (&args.word_regex, args.unicode_words) {
+        (Some(re), _) => Some(WordMode::Regex(re.clone())),
+        (None, true) => Some(WordMode::Unicode),
+        (None, false) => None,
     };
-    let file_num = args.files.len();
-    for filename in args.files.iter() {
-        match open(&filename) {
-            Err(err) => eprintln!("failed to open {}: {}\n", filename, err),
-            Ok(handle) => {
-                let info: FileInfo = count(handle)?;
-                info.print_info(
-                    filename.to_string(),
-                    args.lines,
-                    args.words,
-                    args.bytes,
-                    args.chars,
-                )?;
-                total.lines += info.lines;
-                total.words += info.words;
-                total.bytes += info.bytes;
-                total.chars += info.chars;
-            }
-        }
-    }
-    if file_num > 1 {
-        total.print_info(
-            "total".to_string(),
-            args.lines,
-            args.words,
-            args.bytes,
-            args.chars,
-        )?;
+    let opts = CountOptions {
+        // Runs of word characters make a better frequency table than
+        // whitespace-separated tokens with punctuation attached.
+        stats: args.stats.then(|| StatsOptions {
+            words: match &args.stats_word_regex {
+                Some(re) => WordMode::Regex(re.clone()),
+                None => words
+                    .clone()
+                    .unwrap_or_else(|| WordMode::Regex(Regex::new(r"\w+").unwrap())),
+            },
+            fold_case: args.fold_case,
+        }),
+        words,
+    };
+    let files = input_files(&args)?;
+    let mut total = FileInfo::default();
+    let mut printer = Printer::new(&args)?;
+    let show_total = match args.total {
+        TotalMode::Auto => files.len() > 1,
+        TotalMode::Always | TotalMode::Only => true,
+        TotalMode::Never => false,
+    };
+    let mut pool = rayon::ThreadPoolBuilder::new();
+    if let Some(jobs) = args.jobs {
+        pool = pool.num_threads(jobs.get());
+    }
+    let pool = pool.build()?;
+    // Files are counted a batch at a time, with results collected in input
+    // order, so output does not depend on which file finishes first. Each
+    // batch is printed and its stats merged before the next starts, so only
+    // the counts themselves are kept for text output, which waits to size
+    // its columns, and the structured formats stream.
+    let batch_size = pool.current_num_threads() * BATCH_PER_THREAD;
+    let mut total_stats = Stats::default();
+    for batch in files.chunks(batch_size) {
+        let results: Vec<_> =
+            pool.install(|| batch.par_iter().map(|f| count_file(f, &opts)).collect());
+        for (filename, result) in batch.iter().zip(results) {
+            let info = match result {
+                Ok((info, stats)) => {
+                    if let Some(stats) = stats {
+                        total_stats = total_stats.merge(stats);
+                    }
+                    info
+                }
+                Err(msg) => {
+                    eprintln!("{}", msg);
+                    continue;
+                }
+            };
+            if args.total != TotalMode::Only {
+                printer.print(Some(filename), &info)?;
+            }
+            total = total + info;
+        }
+    }
+    if show_total {
+        printer.print_total(&total, args.total == TotalMode::Only)?;
+    }
+    printer.finish()?;
+    if args.stats {
+        match args.format {
+            Format::Json => total_stats.print_json(args.top)?,
+            _ => total_stats.print_text(args.top),
+        }
     }
     Ok(())
 }
@@ -181,3 +1124,188 @@ fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
         _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        count, count_chunked, count_lines, count_path, join_chunks, CountOptions, FileInfo,
+        StatsOptions, WordMode,
+    };
+    use regex::bytes::Regex;
+    use std::io::Cursor;
+    use std::path::Path;
+
+    #[test]
+    fn test_count() {
+        let text = "I don't want the world. I just want your half.\r\n";
+        let info = count(Cursor::new(text));
+        assert!(info.is_ok());
+        let expects = FileInfo {
+            lines: 1,
+            words: 10,
+            chars: 48,
+            bytes: 48,
+            max_line_length: 46,
+            longest_line: 1,
+            blank_lines: 0,
+            line_width_total: 46,
+            text_lines: 1,
+        };
+        assert_eq!(info.unwrap(), expects);
+    }
+
+    #[test]
+    fn test_count_no_trailing_newline() {
+        let info = count(Cursor::new("one\ntwo")).unwrap();
+        assert_eq!(info.lines, 1);
+        assert_eq!(info.words, 2);
+    }
+
+    #[test]
+    fn test_count_neutral_bytes() {
+        // A lone control byte or invalid byte between spaces is no word.
+        let info = count(Cursor::new(&b"a \x01 b\n"[..])).unwrap();
+        assert_eq!(info.words, 2);
+        let info = count(Cursor::new(&b"\xc3\xa9 \xff b\n"[..])).unwrap();
+        assert_eq!(info.words, 2);
+        let info = count(Cursor::new(&b"a\x01b \xffc\n"[..])).unwrap();
+        assert_eq!(info.words, 2);
+    }
+
+    #[test]
+    fn test_count_invalid_utf8() {
+        // Invalid bytes are neither chars nor word boundaries, as in GNU wc.
+        let text: &[u8] = b"ab\xff\xfecd \xc3\xa9\xc3 x\xa0y\n";
+        let info = count(Cursor::new(text)).unwrap();
+        let expects = FileInfo {
+            lines: 1,
+            words: 3,
+            chars: 10,
+            bytes: 15,
+            max_line_length: 9,
+            longest_line: 1,
+            blank_lines: 0,
+            line_width_total: 9,
+            text_lines: 1,
+        };
+        assert_eq!(info, expects);
+    }
+
+    #[test]
+    fn test_count_unicode_whitespace() {
+        let info = count(Cursor::new("a\u{3000}b\u{a0}c\x0bd\n")).unwrap();
+        assert_eq!(info.words, 4);
+        assert_eq!(info.chars, 8);
+    }
+
+    #[test]
+    fn test_count_line_metrics() {
+        let text = "short\n\n\t\u{4e2d}\u{6587}x\n  \nwide\tline\rab\nlast";
+        let info = count(Cursor::new(text)).unwrap();
+        assert_eq!(info.lines, 5);
+        assert_eq!(info.text_lines, 6);
+        // "\t" to column 8, two wide chars, then "x".
+        assert_eq!(info.max_line_length, 13);
+        assert_eq!(info.longest_line, 3);
+        assert_eq!(info.blank_lines, 2);
+        assert_eq!(info.line_width_total, 5 + 13 + 2 + 12 + 4);
+    }
+
+    #[test]
+    fn test_count_split_buffers() {
+        // A multi-byte character split across reads is still one char.
+        let text = "\u{e9}t\u{e9} caf\u{e9}\n".as_bytes();
+        for split in 0..text.len() {
+            let reader = std::io::BufReader::with_capacity(split.max(1), Cursor::new(text));
+            let info = count(reader).unwrap();
+            assert_eq!(info.chars, 9);
+            assert_eq!(info.words, 2);
+        }
+    }
+
+    #[test]
+    fn test_count_chunked() {
+        // Small chunks must add up to the same counts as one pass.
+        for filename in ["tests/inputs/atlamal.txt", "tests/inputs/invalid.bin"] {
+            let text = std::fs::read(filename).unwrap();
+            let expects = count(Cursor::new(&text)).unwrap();
+            for chunk_size in [1, 7, 40, 1000] {
+                let info = count_chunked(Path::new(filename), text.len() as u64, chunk_size, count)
+                    .unwrap()
+                    .into_iter()
+                    .fold(FileInfo::default(), FileInfo::append);
+                assert_eq!(info, expects, "{} in chunks of {}", filename, chunk_size);
+            }
+        }
+    }
+
+    fn stats_options(words: WordMode) -> CountOptions {
+        CountOptions {
+            words: None,
+            stats: Some(StatsOptions {
+                words,
+                fold_case: true,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_count_stats() {
+        let opts = stats_options(WordMode::Regex(Regex::new(r"\w+").unwrap()));
+        let text = "The cat\n\nthe CAT sat\n\tend";
+        let (info, stats) = count_lines(Cursor::new(text), &opts).unwrap();
+        assert_eq!(info, count(Cursor::new(text)).unwrap());
+        let stats = stats.unwrap();
+        assert_eq!(
+            stats
+                .line_widths
+                .iter()
+                .map(|(&w, &n)| (w, n))
+                .collect::<Vec<_>>(),
+            [(0, 1), (7, 1), (11, 2)]
+        );
+        assert_eq!(stats.top_words(2), [("cat", 2), ("the", 2)]);
+        assert_eq!(stats.percentile(50), 7);
+        assert_eq!(stats.percentile(90), 11);
+        assert_eq!(stats.max_width(), 11);
+        assert_eq!(stats.histogram()[0], (0, 1, 1));
+
+        let atlamal = "tests/inputs/atlamal.txt";
+        let text = std::fs::read(atlamal).unwrap();
+        let expects = count_lines(Cursor::new(&text), &opts).unwrap();
+        let chunks = count_chunked(Path::new(atlamal), text.len() as u64, 20, |c| {
+            count_lines(c, &opts)
+        });
+        assert_eq!(join_chunks(chunks.unwrap()), expects);
+    }
+
+    #[test]
+    fn test_count_word_modes() {
+        let text = "don't stop-me (now)\n\u{79c1}\u{306f}\u{732b}\u{3067}\u{3059}\u{3002}\n";
+        let words = |words| {
+            let opts = CountOptions {
+                words: Some(words),
+                stats: None,
+            };
+            count_lines(Cursor::new(text), &opts).unwrap().0.words
+        };
+        assert_eq!(count(Cursor::new(text)).unwrap().words, 4);
+        assert_eq!(words(WordMode::Regex(Regex::new("[a-z]+").unwrap())), 5);
+        // "don't", "stop", "me", "now", then one word per CJK character.
+        assert_eq!(words(WordMode::Unicode), 9);
+    }
+
+    #[test]
+    fn test_count_path_sum() {
+        let files = ["tests/inputs/fox.txt", "tests/inputs/atlamal.txt"];
+        let infos: Vec<FileInfo> = files.iter().map(|f| count_path(f).unwrap()).collect();
+        assert_eq!(infos[0].words, 9);
+        let total: FileInfo = infos.iter().copied().sum();
+        assert_eq!(total, infos[0] + infos[1]);
+        assert_eq!((total.lines, total.words, total.bytes), (5, 38, 225));
+        // The longest line is line 1 of fox.txt.
+        assert_eq!((total.max_line_length, total.longest_line), (50, 1));
+        assert_eq!(FileInfo::default() + infos[1], infos[1]);
+        assert!(count_path("tests/inputs/missing.txt").is_err());
+    }
+}
@@ -8,6 +8,7 @@ const PRG: &str = "wcr";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
+const INVALID: &str = "tests/inputs/invalid.bin";
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -161,8 +162,7 @@ fn atlamal_bytes_lines() -> Result<()> {
 #[test]
 fn atlamal_stdin() -> Result<()> {
     let input = fs::read_to_string(ATLAMAL)?;
-    let expected =
-        fs::read_to_string("tests/expected/atlamal.txt.stdin.out")?;
+    let expected = fs::read_to_string("tests/expected/atlamal.txt.stdin.out")?;
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .assert()
@@ -211,3 +211,371 @@ fn test_all_words_lines() -> Result<()> {
 fn test_all_bytes_lines() -> Result<()> {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn invalid_utf8() -> Result<()> {
+    run(&[INVALID], "tests/expected/invalid.bin.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn invalid_utf8_chars() -> Result<()> {
+    run(&["-lwm", INVALID], "tests/expected/invalid.bin.lwm.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_unreadable_file() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", FOX])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("tests/inputs: Is a directory"))
+        .stdout(predicate::str::contains(FOX));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn atlamal_max_line_length() -> Result<()> {
+    run(&["-L", ATLAMAL], "tests/expected/atlamal.txt.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_lines_max_line_length() -> Result<()> {
+    run(&["-lL", EMPTY, FOX, ATLAMAL], "tests/expected/all.lL.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_extra_metrics() -> Result<()> {
+    run(
+        &[
+            "--longest-line-number",
+            "--blank-lines",
+            "--avg-line-length",
+            EMPTY,
+            FOX,
+            ATLAMAL,
+        ],
+        "tests/expected/all.extra.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_format() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--format", "xml", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'xml'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_json() -> Result<()> {
+    run(
+        &["--format", "json", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.json.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_lwm_csv() -> Result<()> {
+    run(
+        &["--format", "csv", "-lwm", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.lwm.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn total_only_tsv() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--format", "tsv", "--total", "only", "-l", FOX, ATLAMAL])
+        .assert()
+        .success()
+        .stdout("file\tlines\n\t5\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn atlamal_stdin_tsv() -> Result<()> {
+    let input = fs::read_to_string(ATLAMAL)?;
+    let expected = fs::read_to_string("tests/expected/atlamal.txt.stdin.tsv.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--format", "tsv"])
+        .write_stdin(input)
+        .assert()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_total() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--total", "sometimes", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'sometimes'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_total_only() -> Result<()> {
+    run(
+        &["--total=only", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.total-only.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_lines_total_only() -> Result<()> {
+    run(
+        &["-l", "--total=only", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.l.total-only.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_total_never() -> Result<()> {
+    run(
+        &["--total=never", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.total-never.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_total_always() -> Result<()> {
+    run(
+        &["--total=always", FOX],
+        "tests/expected/fox.txt.total-always.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dir_recursive() -> Result<()> {
+    run(&["-r", "tests/inputs"], "tests/expected/inputs.r.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn dir_recursive_include() -> Result<()> {
+    run(
+        &["-r", "-l", "--include", "*.txt", "tests"],
+        "tests/expected/tests.r.l.include.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_include_without_recursive() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--include", "*.txt", "tests"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--recursive"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "-"])
+        .write_stdin(format!("{}\0{}\0{}\0", EMPTY, FOX, ATLAMAL))
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_skips_empty_name() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "-"])
+        .write_stdin(format!("{}\0\0", FOX))
+        .assert()
+        .success()
+        .stderr("-:2: invalid zero-length file name\n")
+        .stdout(" 1  9 48 tests/inputs/fox.txt\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_with_files() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "-", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn width_fits_large_stdin_count() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "-", FOX])
+        .write_stdin("a".repeat(10_000_000))
+        .assert()
+        .success()
+        .stdout("10000000\n      48 tests/inputs/fox.txt\n10000048 total\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_one_job() -> Result<()> {
+    run(&["-j", "1", EMPTY, FOX, ATLAMAL], "tests/expected/all.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn many_files_keep_order_across_batches() -> Result<()> {
+    // More files than one batch holds with a single thread.
+    let files: Vec<&str> = [EMPTY, FOX, ATLAMAL].repeat(7);
+    let expected: String = [
+        " 0 tests/inputs/empty.txt\n",
+        " 1 tests/inputs/fox.txt\n",
+        " 4 tests/inputs/atlamal.txt\n",
+    ]
+    .repeat(7)
+    .concat()
+        + "35 total\n";
+    Command::cargo_bin(PRG)?
+        .args(["-j", "1", "-l"])
+        .args(&files)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_zero_jobs() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--jobs", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '0'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stats() -> Result<()> {
+    run(
+        &["--stats", ATLAMAL, FOX],
+        "tests/expected/atlamal.fox.stats.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn stats_top_word_regex() -> Result<()> {
+    run(
+        &[
+            "--stats",
+            "--top",
+            "3",
+            "--fold-case",
+            "--stats-word-regex",
+            r"\w+r\w*",
+            ATLAMAL,
+        ],
+        "tests/expected/atlamal.txt.stats.top3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn stats_json() -> Result<()> {
+    run(
+        &["--format", "json", "--stats", "--top", "2", FOX],
+        "tests/expected/fox.txt.stats.json.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_stats_csv() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--format", "csv", "--stats", FOX])
+        .assert()
+        .failure()
+        .stderr("--stats only supports the text and json formats\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_top_without_stats() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--top", "3", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--stats"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_words() -> Result<()> {
+    run(
+        &["--unicode-words", "tests/inputs/japanese.txt"],
+        "tests/expected/japanese.txt.unicode.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn word_regex() -> Result<()> {
+    run(
+        &[
+            "-w",
+            "--word-regex",
+            r"\p{Han}+|\p{Hiragana}+|\w+",
+            "tests/inputs/japanese.txt",
+        ],
+        "tests/expected/japanese.txt.w.regex.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_word_regex_and_unicode_words() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--word-regex", r"\w+", "--unicode-words", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_word_regex() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--word-regex", "(", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"Invalid regex "(""#));
+    Ok(())
+}